@@ -0,0 +1,153 @@
+use crate::models::{Bookmark, ProjectDirectories, StartDirectorySettings};
+use crate::paths::normalize_path;
+use crate::persistence::{load, project_entry_key, update, update_in_background};
+use tauri::AppHandle;
+
+const MAX_RECENT_DIRECTORIES: usize = 20;
+
+pub fn load_directories(app: &AppHandle, key: &str) -> Result<ProjectDirectories, String> {
+    load(app, &project_entry_key(key, "directories"))
+}
+
+fn update_directories<R>(
+    app: &AppHandle,
+    key: &str,
+    update_with: impl FnOnce(&mut ProjectDirectories) -> Result<R, String>,
+) -> Result<R, String> {
+    update(app, &project_entry_key(key, "directories"), update_with)
+}
+
+/// Moves `path` to the front of the project's recent directories and remembers it as
+/// the last visited one. Saved in the background, since it runs on every listing.
+pub fn record_visited_directory(app: &AppHandle, key: &str, path: &str) {
+    let path = normalize_path(path);
+
+    update_in_background(app, project_entry_key(key, "directories"), move |directories: &mut ProjectDirectories| {
+        // The last directory can differ from the first recent one once recents are edited
+        directories.last_directory = Some(path.clone());
+
+        if directories.recent.first() != Some(&path) {
+            directories.recent.retain(|p| p != &path);
+            directories.recent.insert(0, path);
+            directories.recent.truncate(MAX_RECENT_DIRECTORIES);
+        }
+    });
+}
+
+#[tauri::command]
+pub fn list_bookmarks(app: AppHandle, key: String) -> Result<Vec<Bookmark>, String> {
+    Ok(load_directories(&app, &key)?.bookmarks)
+}
+
+#[tauri::command]
+pub fn add_bookmark(
+    app: AppHandle,
+    key: String,
+    path: String,
+    name: Option<String>,
+) -> Result<Vec<Bookmark>, String> {
+    let path = normalize_path(&path);
+    let name = name
+        .filter(|n| !n.trim().is_empty())
+        .unwrap_or_else(|| match path.rsplit('/').next() {
+            Some(last) if !last.is_empty() => last.to_string(),
+            _ => path.clone(),
+        });
+
+    update_directories(&app, &key, |directories| {
+        if directories.bookmarks.iter().any(|b| b.path == path) {
+            return Err(format!("{} is already bookmarked", path));
+        }
+
+        directories.bookmarks.push(Bookmark { name, path });
+        Ok(directories.bookmarks.clone())
+    })
+}
+
+#[tauri::command]
+pub fn remove_bookmark(app: AppHandle, key: String, path: String) -> Result<Vec<Bookmark>, String> {
+    let path = normalize_path(&path);
+
+    update_directories(&app, &key, |directories| {
+        let initial_len = directories.bookmarks.len();
+        directories.bookmarks.retain(|b| b.path != path);
+
+        if directories.bookmarks.len() == initial_len {
+            return Err("Bookmark not found".to_string());
+        }
+
+        Ok(directories.bookmarks.clone())
+    })
+}
+
+#[tauri::command]
+pub fn move_bookmark(
+    app: AppHandle,
+    key: String,
+    from: usize,
+    to: usize,
+) -> Result<Vec<Bookmark>, String> {
+    update_directories(&app, &key, |directories| {
+        if from >= directories.bookmarks.len() || to >= directories.bookmarks.len() {
+            return Err("Bookmark index out of range".to_string());
+        }
+
+        let bookmark = directories.bookmarks.remove(from);
+        directories.bookmarks.insert(to, bookmark);
+        Ok(directories.bookmarks.clone())
+    })
+}
+
+#[tauri::command]
+pub fn list_recent_directories(app: AppHandle, key: String) -> Result<Vec<String>, String> {
+    Ok(load_directories(&app, &key)?.recent)
+}
+
+#[tauri::command]
+pub fn remove_recent_directory(app: AppHandle, key: String, path: String) -> Result<Vec<String>, String> {
+    let path = normalize_path(&path);
+
+    update_directories(&app, &key, |directories| {
+        directories.recent.retain(|p| p != &path);
+        Ok(directories.recent.clone())
+    })
+}
+
+#[tauri::command]
+pub fn clear_recent_directories(app: AppHandle, key: String) -> Result<(), String> {
+    update_directories(&app, &key, |directories| {
+        directories.recent.clear();
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub fn get_start_directory_settings(app: AppHandle, key: String) -> Result<StartDirectorySettings, String> {
+    let directories = load_directories(&app, &key)?;
+
+    Ok(StartDirectorySettings {
+        start_directory: directories.start_directory,
+        remember_last_directory: directories.remember_last_directory,
+        last_directory: directories.last_directory,
+    })
+}
+
+#[tauri::command]
+pub fn set_start_directory_settings(
+    app: AppHandle,
+    key: String,
+    start_directory: Option<String>,
+    remember_last_directory: bool,
+) -> Result<(), String> {
+    // "~" is kept as typed and expanded on the server when the project opens
+    let start_directory = start_directory
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+        .map(|p| if p.starts_with('~') { p } else { normalize_path(&p) });
+
+    update_directories(&app, &key, |directories| {
+        directories.start_directory = start_directory;
+        directories.remember_last_directory = remember_last_directory;
+        Ok(())
+    })
+}
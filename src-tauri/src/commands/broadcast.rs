@@ -0,0 +1,136 @@
+use crate::commands::terminal::{execute_terminal_command, send_terminal_input};
+use crate::models::{BroadcastFailure, BroadcastGroup, BroadcastMember};
+use crate::state::{get_project_by_key, BROADCAST_GROUPS};
+use tauri::AppHandle;
+
+fn group_members(group: &str) -> Result<Vec<BroadcastMember>, String> {
+    let groups = BROADCAST_GROUPS
+        .lock()
+        .map_err(|e| format!("Failed to lock broadcast groups: {}", e))?;
+
+    groups
+        .get(group)
+        .cloned()
+        .ok_or_else(|| format!("Broadcast group not found: {}", group))
+}
+
+/// Adds a terminal to a broadcast group, creating the group on first use.
+#[tauri::command]
+pub async fn join_broadcast_group(group: String, key: String, terminal_id: String) -> Result<(), String> {
+    let group = group.trim().to_string();
+    if group.is_empty() {
+        return Err("Group name cannot be empty".to_string());
+    }
+
+    let project = get_project_by_key(&key)?;
+    if !project
+        .terminal_connections
+        .lock()
+        .await
+        .iter()
+        .any(|t| t.id == terminal_id)
+    {
+        return Err("Terminal not found".to_string());
+    }
+
+    let mut groups = BROADCAST_GROUPS
+        .lock()
+        .map_err(|e| format!("Failed to lock broadcast groups: {}", e))?;
+
+    let member = BroadcastMember { key, terminal_id };
+    let members = groups.entry(group).or_default();
+    if !members.contains(&member) {
+        members.push(member);
+    }
+
+    Ok(())
+}
+
+/// Removes a terminal from a broadcast group; the group goes away with its last member.
+#[tauri::command]
+pub fn leave_broadcast_group(group: String, key: String, terminal_id: String) -> Result<(), String> {
+    let mut groups = BROADCAST_GROUPS
+        .lock()
+        .map_err(|e| format!("Failed to lock broadcast groups: {}", e))?;
+
+    let members = groups
+        .get_mut(&group)
+        .ok_or_else(|| format!("Broadcast group not found: {}", group))?;
+
+    let initial_len = members.len();
+    members.retain(|m| m.key != key || m.terminal_id != terminal_id);
+
+    if members.len() == initial_len {
+        return Err("Terminal is not in this group".to_string());
+    }
+    if members.is_empty() {
+        groups.remove(&group);
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_broadcast_groups() -> Result<Vec<BroadcastGroup>, String> {
+    let groups = BROADCAST_GROUPS
+        .lock()
+        .map_err(|e| format!("Failed to lock broadcast groups: {}", e))?;
+
+    Ok(groups
+        .iter()
+        .map(|(name, members)| BroadcastGroup {
+            name: name.clone(),
+            members: members.clone(),
+        })
+        .collect())
+}
+
+/// Sends raw input to every terminal in the group and returns the members it could not reach.
+#[tauri::command]
+pub async fn broadcast_input(group: String, input: String) -> Result<Vec<BroadcastFailure>, String> {
+    let mut failures = Vec::new();
+
+    for member in group_members(&group)? {
+        if let Err(error) =
+            send_terminal_input(member.key.clone(), member.terminal_id.clone(), input.clone()).await
+        {
+            failures.push(BroadcastFailure {
+                key: member.key,
+                terminal_id: member.terminal_id,
+                error,
+            });
+        }
+    }
+
+    Ok(failures)
+}
+
+/// Runs a command in every terminal in the group, like `execute_terminal_command`,
+/// and returns the members it could not reach.
+#[tauri::command]
+pub async fn broadcast_command(
+    app: AppHandle,
+    group: String,
+    command: String,
+) -> Result<Vec<BroadcastFailure>, String> {
+    let mut failures = Vec::new();
+
+    for member in group_members(&group)? {
+        if let Err(error) = execute_terminal_command(
+            app.clone(),
+            member.key.clone(),
+            member.terminal_id.clone(),
+            command.clone(),
+        )
+        .await
+        {
+            failures.push(BroadcastFailure {
+                key: member.key,
+                terminal_id: member.terminal_id,
+                error,
+            });
+        }
+    }
+
+    Ok(failures)
+}
@@ -1,11 +1,26 @@
 use tokio::sync::Mutex;
 
 use crate::auth::build_auth_method;
-use crate::models::{Project, ProjectInfo};
-use crate::ssh::{connect_to_ssh, parse_host_port};
+use crate::commands::bookmarks::load_directories;
+use crate::commands::local_edit::cleanup_opened_files;
+use crate::commands::settings::load_settings;
+use crate::models::{PathCache, Project, ProjectInfo};
+use crate::paths::{expand_home, shell_quote};
+use crate::ssh::{connect_to_ssh, parse_host_port, ConnectionPool, DEFAULT_MAX_SESSIONS};
 use crate::state::{add_project, get_project_by_key, remove_project_by_key, PROJECTS};
 use std::sync::Arc;
 
+/// Label of the window that shows a project: its own window on desktop, the main one on mobile.
+#[cfg(desktop)]
+pub fn project_window_label(key: &str) -> String {
+    format!("remote-{}", key)
+}
+
+#[cfg(mobile)]
+pub fn project_window_label(_key: &str) -> String {
+    "main".to_string()
+}
+
 #[tauri::command]
 pub async fn validate_ssh_connection(
     host: &str,
@@ -70,6 +85,11 @@ pub async fn start_project(
         ));
     }
 
+    let max_sessions = load_settings(&app, &key)?
+        .max_sessions
+        .unwrap_or(DEFAULT_MAX_SESSIONS);
+    let main_connection = Arc::new(client);
+
     // Create the project and store it
     let project = Project {
         key: key.clone(),
@@ -80,8 +100,14 @@ pub async fn start_project(
         key_file,
         public_key_file,
         auth_method,
-        main_connection: Arc::new(client),
+        terminal_pool: Arc::new(Mutex::new(ConnectionPool::new(
+            Arc::clone(&main_connection),
+            max_sessions,
+        ))),
+        main_connection,
         terminal_connections: Arc::new(Mutex::new(Vec::new())),
+        path_cache: Arc::new(Mutex::new(PathCache::default())),
+        opened_files: Arc::new(Mutex::new(Vec::new())),
     };
 
     add_project(project)?;
@@ -93,7 +119,7 @@ pub async fn start_project(
         use tauri::WebviewUrl;
         use tauri::WebviewWindowBuilder;
 
-        let window_label = format!("remote-{}", key);
+        let window_label = project_window_label(&key);
         let url = format!("/remote?key={}", key);
         
         let _ = app.add_capability(include_str!("../../capabilities/default.json"));
@@ -114,15 +140,14 @@ pub async fn start_project(
                     // Spawn async task to handle cleanup
                     tauri::async_runtime::spawn(async move {
                         if let Ok(project) = get_project_by_key(&key_clone) {
-                            // Disconnect terminal connections
-                            let mut terminal_conns = project.terminal_connections.lock().await;
-                            for conn in terminal_conns.drain(..) {
-                                let _ = conn.connection.disconnect();
-                            }
-                            drop(terminal_conns); // Release lock
+                            cleanup_opened_files(&project).await;
+
+                            // Terminal channels end with the connections they run on
+                            project.terminal_connections.lock().await.clear();
+                            project.terminal_pool.lock().await.disconnect_all().await;
 
                             // Disconnect main connection
-                            let _ = project.main_connection.disconnect();
+                            let _ = project.main_connection.disconnect().await;
                         }
 
                         // Remove project from state
@@ -188,18 +213,51 @@ pub async fn get_current_pwd(key: String) -> Result<String, String> {
     }
 }
 
+/// Directory the explorer opens in: the last visited one when remembered,
+/// then the configured start directory, falling back to the login directory.
+#[tauri::command]
+pub async fn get_start_directory(app: tauri::AppHandle, key: String) -> Result<String, String> {
+    let project = get_project_by_key(&key)?;
+    let directories = load_directories(&app, &key)?;
+
+    let mut candidates = Vec::new();
+    if directories.remember_last_directory {
+        candidates.extend(directories.last_directory);
+    }
+    candidates.extend(directories.start_directory);
+
+    for candidate in candidates {
+        let path = match expand_home(&project.main_connection, &project.path_cache, &candidate).await {
+            Ok(path) => path,
+            Err(_) => continue,
+        };
+
+        let exists = project
+            .main_connection
+            .execute(&format!("test -d {}", shell_quote(&path)))
+            .await
+            .map(|result| result.exit_status == 0)
+            .unwrap_or(false);
+
+        if exists {
+            return Ok(path);
+        }
+    }
+
+    get_current_pwd(key).await
+}
+
 #[tauri::command]
 pub async fn close_project(app: tauri::AppHandle, key: String) -> Result<(), String> {
     if let Ok(project) = get_project_by_key(&key) {
-        // Disconnect terminal connections
-        let mut terminal_conns = project.terminal_connections.lock().await;
-        for conn in terminal_conns.drain(..) {
-            let _ = conn.connection.disconnect();
-        }
-        drop(terminal_conns); // Release lock
+        cleanup_opened_files(&project).await;
+
+        // Terminal channels end with the connections they run on
+        project.terminal_connections.lock().await.clear();
+        project.terminal_pool.lock().await.disconnect_all().await;
 
         // Disconnect main connection
-        let _ = project.main_connection.disconnect();
+        let _ = project.main_connection.disconnect().await;
     }
 
     // Remove the project from the global state
@@ -210,7 +268,7 @@ pub async fn close_project(app: tauri::AppHandle, key: String) -> Result<(), Str
     {
         use tauri::Manager;
 
-        let window_label = format!("remote-{}", key);
+        let window_label = project_window_label(&key);
         if let Some(window) = app.get_webview_window(&window_label) {
             window
                 .close()
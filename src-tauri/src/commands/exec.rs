@@ -0,0 +1,220 @@
+use crate::commands::connection::project_window_label;
+use crate::models::{CommandOutputEvent, RunCommandResult};
+use crate::paths::shell_quote;
+use crate::state::get_project_by_key;
+use crate::terminal::buffer::Utf8Stream;
+use crate::terminal::session::signal_name;
+use async_ssh2_tokio::client::Client;
+use russh::client::Msg;
+use russh::{Channel, ChannelMsg, Sig};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use uuid::Uuid;
+
+const DEFAULT_TIMEOUT_SECS: u64 = 60;
+// How long a timed out command gets to exit after TERM before it is killed
+const KILL_GRACE: Duration = Duration::from_secs(2);
+
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+impl OutputStream {
+    fn name(&self) -> &'static str {
+        match self {
+            OutputStream::Stdout => "stdout",
+            OutputStream::Stderr => "stderr",
+        }
+    }
+}
+
+/// How a one-off command ended, without its output.
+#[derive(Default)]
+pub struct RemoteExit {
+    pub exit_status: Option<u32>,
+    pub signal: Option<String>,
+    pub timed_out: bool,
+}
+
+/// Runs `command` on its own channel, writing `stdin` to it first, and hands every decoded
+/// output chunk to `on_output`. Once `timeout` passes the command is sent TERM, then KILL
+/// if it is still running, and the channel is closed.
+pub async fn run_remote_command(
+    connection: &Client,
+    command: &str,
+    stdin: Option<&[u8]>,
+    timeout: Duration,
+    mut on_output: impl FnMut(OutputStream, String),
+) -> Result<RemoteExit, String> {
+    let deadline = tokio::time::Instant::now() + timeout;
+
+    let mut channel = connection
+        .get_channel()
+        .await
+        .map_err(|e| format!("Failed to open channel: {}", e))?;
+
+    channel
+        .exec(true, command)
+        .await
+        .map_err(|e| format!("Failed to run command: {}", e))?;
+
+    // A command that never reads stdin stops the write once the window fills up
+    let written = tokio::time::timeout_at(deadline, async {
+        if let Some(stdin) = stdin {
+            channel
+                .data(stdin)
+                .await
+                .map_err(|e| format!("Failed to write to command: {}", e))?;
+        }
+        // Commands reading stdin would otherwise wait forever
+        let _ = channel.eof().await;
+        Ok::<(), String>(())
+    })
+    .await;
+
+    match written {
+        Ok(result) => result?,
+        Err(_) => {
+            stop_command(&mut channel).await;
+            return Ok(RemoteExit {
+                timed_out: true,
+                ..RemoteExit::default()
+            });
+        }
+    }
+
+    let mut exit = RemoteExit::default();
+    let mut stdout = Utf8Stream::default();
+    let mut stderr = Utf8Stream::default();
+
+    loop {
+        let msg = match tokio::time::timeout_at(deadline, channel.wait()).await {
+            Ok(msg) => msg,
+            Err(_) => {
+                exit.timed_out = true;
+                stop_command(&mut channel).await;
+                break;
+            }
+        };
+
+        match msg {
+            Some(ChannelMsg::Data { data }) => {
+                on_output(OutputStream::Stdout, stdout.push(&data));
+            }
+            // Extended data type 1 is stderr
+            Some(ChannelMsg::ExtendedData { data, ext: 1 }) => {
+                on_output(OutputStream::Stderr, stderr.push(&data));
+            }
+            Some(ChannelMsg::ExitStatus { exit_status }) => {
+                exit.exit_status = Some(exit_status);
+            }
+            Some(ChannelMsg::ExitSignal { signal_name: signal, .. }) => {
+                exit.signal = Some(signal_name(&signal));
+            }
+            Some(ChannelMsg::Close) | None => break,
+            Some(_) => {}
+        }
+    }
+
+    on_output(OutputStream::Stdout, stdout.finish());
+    on_output(OutputStream::Stderr, stderr.finish());
+
+    Ok(exit)
+}
+
+// Asks the command to terminate, kills it when it ignores that and closes the channel
+async fn stop_command(channel: &mut Channel<Msg>) {
+    let _ = channel.signal(Sig::TERM).await;
+    if !wait_for_exit(channel, KILL_GRACE).await {
+        let _ = channel.signal(Sig::KILL).await;
+        wait_for_exit(channel, KILL_GRACE).await;
+    }
+    let _ = channel.close().await;
+}
+
+// Output arriving while the command shuts down is dropped
+async fn wait_for_exit(channel: &mut Channel<Msg>, grace: Duration) -> bool {
+    tokio::time::timeout(grace, async {
+        loop {
+            match channel.wait().await {
+                Some(ChannelMsg::ExitStatus { .. })
+                | Some(ChannelMsg::ExitSignal { .. })
+                | Some(ChannelMsg::Close)
+                | None => break,
+                Some(_) => {}
+            }
+        }
+    })
+    .await
+    .is_ok()
+}
+
+/// Runs a command on the project connection and returns stdout, stderr, exit status and
+/// duration separately. With `stream` set, output is sent as "command-output" events tagged
+/// with `run_id` instead of being collected into the result.
+#[tauri::command]
+pub async fn run_command(
+    app: AppHandle,
+    key: String,
+    command: String,
+    cwd: Option<String>,
+    stdin: Option<String>,
+    timeout_secs: Option<u64>,
+    stream: Option<bool>,
+    run_id: Option<String>,
+) -> Result<RunCommandResult, String> {
+    let project = get_project_by_key(&key)?;
+    let run_id = run_id.unwrap_or_else(|| Uuid::new_v4().to_string());
+    let stream = stream.unwrap_or(false);
+
+    let full_command = match cwd.filter(|c| !c.trim().is_empty()) {
+        Some(cwd) => format!("cd {} && {}", shell_quote(&cwd), command),
+        None => command,
+    };
+
+    let mut stdout = String::new();
+    let mut stderr = String::new();
+    let started = Instant::now();
+
+    let exit = run_remote_command(
+        &project.main_connection,
+        &full_command,
+        stdin.as_deref().map(str::as_bytes),
+        Duration::from_secs(timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS)),
+        |output, data| {
+            if data.is_empty() {
+                return;
+            }
+
+            if stream {
+                let _ = app.emit_to(
+                    project_window_label(&key).as_str(),
+                    "command-output",
+                    CommandOutputEvent {
+                        key: key.clone(),
+                        run_id: run_id.clone(),
+                        stream: output.name().to_string(),
+                        data,
+                    },
+                );
+            } else {
+                match output {
+                    OutputStream::Stdout => stdout.push_str(&data),
+                    OutputStream::Stderr => stderr.push_str(&data),
+                }
+            }
+        },
+    )
+    .await?;
+
+    Ok(RunCommandResult {
+        run_id,
+        stdout,
+        stderr,
+        exit_status: exit.exit_status,
+        signal: exit.signal,
+        duration_ms: started.elapsed().as_millis() as u64,
+        timed_out: exit.timed_out,
+    })
+}
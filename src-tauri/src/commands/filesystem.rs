@@ -1,9 +1,22 @@
-use crate::models::DirEntry;
-use crate::state::PROJECTS;
+use crate::commands::bookmarks::record_visited_directory;
+use crate::models::{CachedListing, CompletionEntry, DirEntry, PathCache, PathCompletion};
+use crate::paths::{expand_home, join_path, normalize_path, real_path, shell_quote};
+use crate::state::{get_project_by_key, PROJECTS};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+// How long a directory listing is reused while the user keeps typing
+const LISTING_CACHE_TTL: Duration = Duration::from_secs(10);
+const MAX_CACHED_LISTINGS: usize = 64;
+const MAX_COMPLETIONS: usize = 200;
 
 #[tauri::command]
-pub async fn get_dir_contents(key: String, path: String) -> Result<Vec<DirEntry>, String> {
+pub async fn get_dir_contents(
+    app: tauri::AppHandle,
+    key: String,
+    path: String,
+) -> Result<Vec<DirEntry>, String> {
     let connection = {
         let projects = PROJECTS
             .lock()
@@ -54,11 +67,7 @@ pub async fn get_dir_contents(key: String, path: String) -> Result<Vec<DirEntry>
                 let target = name_and_target[arrow_pos + 4..].trim().to_string();
                 
                 // Resolve the target path (handle relative paths)
-                let resolved_target = if target.starts_with('/') {
-                    target.clone()
-                } else {
-                    format!("{}/{}", path, target)
-                };
+                let resolved_target = join_path(&path, &target);
 
                 // Get the stats of the target file/directory
                 match get_target_stats(&connection, &resolved_target).await {
@@ -104,6 +113,9 @@ pub async fn get_dir_contents(key: String, path: String) -> Result<Vec<DirEntry>
         }
     }
 
+    // Remembering recents is best effort and must not break navigation
+    record_visited_directory(&app, &key, &path);
+
     Ok(entries)
 }
 
@@ -160,6 +172,7 @@ pub async fn create_file(key: String, path: String) -> Result<(), String> {
         .map_err(|e| format!("Failed to create file: {}", e))?;
 
     if result.exit_status == 0 {
+        forget_cached_listings(&key).await;
         Ok(())
     } else {
         Err(format!("Command failed: {}", result.stderr))
@@ -188,6 +201,7 @@ pub async fn create_folder(key: String, path: String) -> Result<(), String> {
         .map_err(|e| format!("Failed to create folder: {}", e))?;
 
     if result.exit_status == 0 {
+        forget_cached_listings(&key).await;
         Ok(())
     } else {
         Err(format!("Command failed: {}", result.stderr))
@@ -221,8 +235,190 @@ pub async fn delete_item(key: String, path: String, is_dir: bool) -> Result<(),
         .map_err(|e| format!("Failed to delete item: {}", e))?;
 
     if result.exit_status == 0 {
+        forget_cached_listings(&key).await;
         Ok(())
     } else {
         Err(format!("Command failed: {}", result.stderr))
     }
-}
\ No newline at end of file
+}
+
+#[tauri::command]
+pub async fn complete_path(
+    key: String,
+    partial: String,
+    cwd: Option<String>,
+) -> Result<Vec<PathCompletion>, String> {
+    let project = get_project_by_key(&key)?;
+    let connection = Arc::clone(&project.main_connection);
+
+    // Split "/usr/lo" into the directory to list ("/usr/") and the prefix to match ("lo")
+    let (typed_dir, prefix) = match partial.rfind('/') {
+        Some(pos) => (partial[..=pos].to_string(), partial[pos + 1..].to_string()),
+        None if partial == "~" => ("~/".to_string(), String::new()),
+        // "~ro" may be half of a user name; expanding it would fail on every keystroke
+        None if partial.starts_with('~') => return Ok(Vec::new()),
+        None => (String::new(), partial.clone()),
+    };
+
+    let expanded = expand_home(&connection, &project.path_cache, &typed_dir).await?;
+
+    let list_dir = if expanded.starts_with('/') {
+        normalize_path(&expanded)
+    } else {
+        match cwd {
+            Some(cwd) => join_path(&cwd, &expanded),
+            None => return Ok(Vec::new()),
+        }
+    };
+
+    let entries = list_completion_entries(&connection, &project.path_cache, &list_dir).await?;
+
+    let show_hidden = prefix.starts_with('.');
+    let mut matches: Vec<&CompletionEntry> = entries
+        .iter()
+        .filter(|e| e.name.starts_with(&prefix))
+        .filter(|e| show_hidden || !e.name.starts_with('.'))
+        .collect();
+
+    // Directories first, then alphabetical
+    matches.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+
+    let completions = matches
+        .into_iter()
+        .take(MAX_COMPLETIONS)
+        .map(|e| {
+            let marker = if e.is_dir {
+                "/"
+            } else if e.is_linked {
+                "@"
+            } else if e.is_executable {
+                "*"
+            } else {
+                ""
+            };
+
+            let mut path = format!("{}{}", typed_dir, e.name);
+            if e.is_dir {
+                path.push('/');
+            }
+
+            PathCompletion {
+                name: e.name.clone(),
+                path,
+                is_dir: e.is_dir,
+                is_linked: e.is_linked,
+                marker: marker.to_string(),
+            }
+        })
+        .collect();
+
+    Ok(completions)
+}
+
+// Listings change after a create or delete, so completion must fetch them again
+async fn forget_cached_listings(key: &str) {
+    if let Ok(project) = get_project_by_key(key) {
+        project.path_cache.lock().await.listings.clear();
+    }
+}
+
+async fn list_completion_entries(
+    connection: &Arc<async_ssh2_tokio::client::Client>,
+    cache: &Arc<Mutex<PathCache>>,
+    dir: &str,
+) -> Result<Vec<CompletionEntry>, String> {
+    let dir = if dir.len() > 1 {
+        dir.trim_end_matches('/')
+    } else {
+        dir
+    };
+
+    if let Some(listing) = cache.lock().await.listings.get(dir) {
+        if listing.fetched_at.elapsed() < LISTING_CACHE_TTL {
+            return Ok(listing.entries.clone());
+        }
+    }
+
+    // %y is the entry type, %Y the type after following symlinks, %m the octal mode
+    let command = format!(
+        "find {} -mindepth 1 -maxdepth 1 -printf '%y|%Y|%m|%f\\n'",
+        shell_quote(dir)
+    );
+    let result = connection
+        .execute(&command)
+        .await
+        .map_err(|e| format!("Failed to list directory: {}", e))?;
+
+    if result.exit_status != 0 && result.stdout.is_empty() {
+        return Err(format!("Command failed: {}", result.stderr));
+    }
+
+    let entries: Vec<CompletionEntry> = result
+        .stdout
+        .lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.splitn(4, '|').collect();
+            if parts.len() < 4 {
+                return None;
+            }
+            let mode = u32::from_str_radix(parts[2], 8).unwrap_or(0);
+            Some(CompletionEntry {
+                name: parts[3].to_string(),
+                is_dir: parts[1] == "d",
+                is_linked: parts[0] == "l",
+                is_executable: parts[1] == "f" && mode & 0o111 != 0,
+            })
+        })
+        .collect();
+
+    let mut cache = cache.lock().await;
+    if cache.listings.len() >= MAX_CACHED_LISTINGS {
+        // Drop the oldest listing to keep the cache bounded
+        if let Some(oldest) = cache
+            .listings
+            .iter()
+            .min_by_key(|(_, l)| l.fetched_at)
+            .map(|(k, _)| k.clone())
+        {
+            cache.listings.remove(&oldest);
+        }
+    }
+    cache.listings.insert(
+        dir.to_string(),
+        CachedListing {
+            fetched_at: Instant::now(),
+            entries: entries.clone(),
+        },
+    );
+
+    Ok(entries)
+}
+
+#[tauri::command]
+pub async fn resolve_path(
+    key: String,
+    path: String,
+    cwd: Option<String>,
+    canonical: Option<bool>,
+) -> Result<String, String> {
+    let project = get_project_by_key(&key)?;
+    let connection = Arc::clone(&project.main_connection);
+
+    let expanded = expand_home(&connection, &project.path_cache, path.trim()).await?;
+
+    let absolute = if expanded.starts_with('/') {
+        normalize_path(&expanded)
+    } else {
+        let base = match cwd {
+            Some(cwd) => expand_home(&connection, &project.path_cache, &cwd).await?,
+            None => expand_home(&connection, &project.path_cache, "~").await?,
+        };
+        join_path(&base, &expanded)
+    };
+
+    if canonical.unwrap_or(true) {
+        real_path(&connection, &absolute).await
+    } else {
+        Ok(absolute)
+    }
+}
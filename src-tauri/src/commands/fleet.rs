@@ -0,0 +1,217 @@
+use crate::auth::build_auth_method;
+use crate::commands::exec::{run_remote_command, OutputStream};
+use crate::models::{FleetHostResult, SavedProject};
+use crate::paths::shell_quote;
+use crate::persistence::load;
+use crate::ssh::{connect_to_ssh, parse_host_port};
+use crate::state::get_project_by_key;
+use async_ssh2_tokio::client::Client;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+use tokio::sync::Semaphore;
+
+const DEFAULT_CONCURRENCY: usize = 8;
+const DEFAULT_HOST_TIMEOUT_SECS: u64 = 60;
+
+// Open projects lend their main connection; others get one for this run only, which the
+// returned flag marks for disconnecting. Also returns the project name and host.
+async fn fleet_connection(
+    app: &AppHandle,
+    key: &str,
+    timeout: Duration,
+) -> Result<(Arc<Client>, bool, String, String), String> {
+    if let Ok(project) = get_project_by_key(key) {
+        return Ok((
+            Arc::clone(&project.main_connection),
+            false,
+            project.name.clone(),
+            project.host.clone(),
+        ));
+    }
+
+    let saved: Option<SavedProject> = load(app, key)?;
+    let saved = saved.ok_or_else(|| "Project not found".to_string())?;
+
+    let host_port = parse_host_port(&saved.host);
+    let auth = build_auth_method(
+        &saved.auth_method,
+        saved.password.as_deref(),
+        saved.key_file.as_deref(),
+        saved.public_key_file.as_deref(),
+    )?;
+    let client = connect_to_ssh(
+        &host_port.hostname,
+        host_port.port,
+        &saved.user,
+        auth,
+        timeout.as_secs().max(1),
+    )
+    .await?;
+
+    Ok((Arc::new(client), true, saved.name, saved.host))
+}
+
+async fn run_on_host(
+    app: &AppHandle,
+    key: &str,
+    command: &str,
+    timeout: Duration,
+) -> FleetHostResult {
+    let started = Instant::now();
+    let mut result = FleetHostResult {
+        key: key.to_string(),
+        ..FleetHostResult::default()
+    };
+
+    // Connecting and running share one deadline, so a slow handshake shortens the run
+    let deadline = tokio::time::Instant::now() + timeout;
+
+    let connected = tokio::time::timeout_at(deadline, fleet_connection(app, key, timeout)).await;
+    let (connection, owned) = match connected {
+        Ok(Ok((connection, owned, name, host))) => {
+            result.name = name;
+            result.host = host;
+            (connection, owned)
+        }
+        Ok(Err(e)) => {
+            result.error = Some(e);
+            result.duration_ms = started.elapsed().as_millis() as u64;
+            return result;
+        }
+        Err(_) => {
+            result.timed_out = true;
+            result.error = Some("Timed out connecting".to_string());
+            result.duration_ms = started.elapsed().as_millis() as u64;
+            return result;
+        }
+    };
+
+    // The command is stopped on the server once the remaining time runs out
+    let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+    let exit = run_remote_command(&connection, command, None, remaining, |output, data| {
+        match output {
+            OutputStream::Stdout => result.stdout.push_str(&data),
+            OutputStream::Stderr => result.stderr.push_str(&data),
+        }
+    })
+    .await;
+
+    if owned {
+        let _ = connection.disconnect().await;
+    }
+
+    match exit {
+        Ok(exit) => {
+            result.exit_status = exit.exit_status;
+            result.signal = exit.signal;
+            result.timed_out = exit.timed_out;
+        }
+        Err(e) => result.error = Some(e),
+    }
+
+    result.duration_ms = started.elapsed().as_millis() as u64;
+    result
+}
+
+/// Runs a command on each of the given saved projects, at most `concurrency` at a time.
+/// Open projects reuse their connection. `timeout_secs` covers connecting and running on
+/// one host. Results come back in the order of `keys`.
+#[tauri::command]
+pub async fn run_fleet_command(
+    app: AppHandle,
+    keys: Vec<String>,
+    command: String,
+    cwd: Option<String>,
+    concurrency: Option<usize>,
+    timeout_secs: Option<u64>,
+) -> Result<Vec<FleetHostResult>, String> {
+    if command.trim().is_empty() {
+        return Err("Command cannot be empty".to_string());
+    }
+
+    let command = match cwd.filter(|c| !c.trim().is_empty()) {
+        Some(cwd) => format!("cd {} && {}", shell_quote(&cwd), command),
+        None => command,
+    };
+    let command = Arc::new(command);
+    let timeout = Duration::from_secs(timeout_secs.unwrap_or(DEFAULT_HOST_TIMEOUT_SECS).max(1));
+    let permits = Arc::new(Semaphore::new(concurrency.unwrap_or(DEFAULT_CONCURRENCY).max(1)));
+
+    let mut tasks = Vec::with_capacity(keys.len());
+    for key in keys {
+        let app = app.clone();
+        let command = Arc::clone(&command);
+        let permits = Arc::clone(&permits);
+
+        tasks.push(tauri::async_runtime::spawn(async move {
+            let _permit = permits.acquire_owned().await;
+            run_on_host(&app, &key, &command, timeout).await
+        }));
+    }
+
+    let mut results = Vec::with_capacity(tasks.len());
+    for task in tasks {
+        results.push(task.await.map_err(|e| format!("Fleet task failed: {}", e))?);
+    }
+
+    Ok(results)
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn fleet_csv(results: &[FleetHostResult]) -> String {
+    let mut csv = String::from(
+        "key,name,host,exit_status,signal,timed_out,duration_ms,error,stdout,stderr\n",
+    );
+
+    for r in results {
+        let row = [
+            csv_field(&r.key),
+            csv_field(&r.name),
+            csv_field(&r.host),
+            r.exit_status.map(|s| s.to_string()).unwrap_or_default(),
+            csv_field(r.signal.as_deref().unwrap_or_default()),
+            r.timed_out.to_string(),
+            r.duration_ms.to_string(),
+            csv_field(r.error.as_deref().unwrap_or_default()),
+            csv_field(&r.stdout),
+            csv_field(&r.stderr),
+        ];
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+
+    csv
+}
+
+/// Writes fleet results to a new local file as "json" or "csv"; existing files are left alone.
+#[tauri::command]
+pub fn export_fleet_results(
+    results: Vec<FleetHostResult>,
+    format: String,
+    path: String,
+) -> Result<(), String> {
+    let contents = match format.to_lowercase().as_str() {
+        "json" => serde_json::to_string_pretty(&results)
+            .map_err(|e| format!("Failed to serialize results: {}", e))?,
+        "csv" => fleet_csv(&results),
+        other => return Err(format!("Unsupported export format: {}", other)),
+    };
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to create {}: {}", path, e))?;
+    file.write_all(contents.as_bytes())
+        .map_err(|e| format!("Failed to write {}: {}", path, e))
+}
@@ -0,0 +1,199 @@
+use crate::commands::settings::load_settings;
+use crate::models::{CommandHistory, HistoryEntry};
+use crate::persistence::{load, project_entry_key, update, update_in_background};
+use crate::terminal::shell_integration::now_millis;
+use tauri::AppHandle;
+
+const MAX_HISTORY_ENTRIES: usize = 1000;
+const DEFAULT_HISTORY_RESULTS: usize = 50;
+
+// Names whose value, when assigned or passed as an option, is most likely a credential
+const SECRET_KEYWORDS: &[&str] = &[
+    "password",
+    "passwd",
+    "passphrase",
+    "secret",
+    "token",
+    "api_key",
+    "apikey",
+    "api-key",
+    "access_key",
+    "private_key",
+    "credentials",
+];
+
+fn load_history(app: &AppHandle, key: &str) -> Result<CommandHistory, String> {
+    load(app, &project_entry_key(key, "history"))
+}
+
+fn update_history<R>(
+    app: &AppHandle,
+    key: &str,
+    update_with: impl FnOnce(&mut CommandHistory) -> Result<R, String>,
+) -> Result<R, String> {
+    update(app, &project_entry_key(key, "history"), update_with)
+}
+
+/// Adds a command sent to a terminal to the project's history, moving an identical
+/// entry to the front. Commands that look like they carry credentials are skipped
+/// unless the project keeps them. The history is saved in the background.
+pub fn record_command(
+    app: &AppHandle,
+    key: &str,
+    command: &str,
+    password: Option<&str>,
+) -> Result<(), String> {
+    let command = command.trim().to_string();
+    if command.is_empty() {
+        return Ok(());
+    }
+
+    if !load_settings(app, key)?.record_secret_commands && looks_secret(&command, password) {
+        return Ok(());
+    }
+
+    update_in_background(app, project_entry_key(key, "history"), move |history: &mut CommandHistory| {
+        let count = match history.entries.iter().position(|e| e.command == command) {
+            Some(index) => history.entries.remove(index).count + 1,
+            None => 1,
+        };
+
+        history.entries.insert(
+            0,
+            HistoryEntry {
+                command,
+                count,
+                last_used: now_millis(),
+            },
+        );
+        history.entries.truncate(MAX_HISTORY_ENTRIES);
+    });
+
+    Ok(())
+}
+
+/// Heuristic check for commands that contain a credential, like `--password=...`,
+/// `export API_TOKEN=...`, an `Authorization` header or the project password itself.
+fn looks_secret(command: &str, password: Option<&str>) -> bool {
+    if let Some(password) = password {
+        if password.len() >= 4 && command.contains(password) {
+            return true;
+        }
+    }
+
+    let lower = command.to_lowercase();
+
+    if lower.contains("authorization:") || lower.contains("bearer ") || lower.contains("sshpass -p") {
+        return true;
+    }
+
+    // AWS access key ids
+    if command.match_indices("AKIA").any(|(index, _)| {
+        let rest = &command.as_bytes()[index + 4..];
+        rest.len() >= 16 && rest[..16].iter().all(|b| b.is_ascii_uppercase() || b.is_ascii_digit())
+    }) {
+        return true;
+    }
+
+    SECRET_KEYWORDS.iter().any(|keyword| {
+        lower.match_indices(keyword).any(|(index, _)| {
+            // Rest of the name, e.g. "token" in "github_token_rw"
+            let rest = lower[index + keyword.len()..]
+                .trim_start_matches(|c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+
+            if rest.starts_with('=') || rest.starts_with(':') {
+                return true;
+            }
+
+            // Options like `--password hunter2`
+            lower[..index].ends_with('-')
+                && rest.starts_with(' ')
+                && rest
+                    .trim_start()
+                    .chars()
+                    .next()
+                    .is_some_and(|c| c != '-')
+        })
+    })
+}
+
+/// Scores `candidate` against `query` when every query character appears in order,
+/// favouring consecutive characters and matches at word starts. Case-insensitive.
+fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let candidate: Vec<char> = candidate.to_lowercase().chars().collect();
+    let mut score = 0i64;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+
+    for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = (position..candidate.len()).find(|&i| candidate[i] == q)?;
+
+        score += 1;
+        if previous.is_some_and(|p| p + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || !candidate[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        // Gaps cost a little so tighter matches rank first
+        score -= (found - position).min(10) as i64;
+
+        previous = Some(found);
+        position = found + 1;
+    }
+
+    Some(score)
+}
+
+/// Returns the project's command history, most recent first, or the best fuzzy
+/// matches for `query` when one is given.
+#[tauri::command]
+pub fn search_command_history(
+    app: AppHandle,
+    key: String,
+    query: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<HistoryEntry>, String> {
+    let history = load_history(&app, &key)?;
+    let limit = limit.unwrap_or(DEFAULT_HISTORY_RESULTS);
+
+    let query = match query.filter(|q| !q.trim().is_empty()) {
+        Some(query) => query,
+        None => return Ok(history.entries.into_iter().take(limit).collect()),
+    };
+
+    let mut matches: Vec<(i64, usize, HistoryEntry)> = history
+        .entries
+        .into_iter()
+        .enumerate()
+        .filter_map(|(rank, entry)| {
+            fuzzy_score(&query, &entry.command).map(|score| (score, rank, entry))
+        })
+        .collect();
+
+    // Best score first; ties go to the more recently used command
+    matches.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+    Ok(matches.into_iter().take(limit).map(|(_, _, entry)| entry).collect())
+}
+
+#[tauri::command]
+pub fn remove_history_entry(app: AppHandle, key: String, command: String) -> Result<(), String> {
+    update_history(&app, &key, |history| {
+        let initial_len = history.entries.len();
+        history.entries.retain(|e| e.command != command);
+
+        if history.entries.len() == initial_len {
+            return Err("History entry not found".to_string());
+        }
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub fn clear_command_history(app: AppHandle, key: String) -> Result<(), String> {
+    update_history(&app, &key, |history| {
+        *history = CommandHistory::default();
+        Ok(())
+    })
+}
@@ -0,0 +1,271 @@
+use crate::commands::connection::project_window_label;
+use crate::models::{OpenedFile, Project, RemoteFileEvent, RemoteStamp};
+use crate::paths::shell_quote;
+use crate::state::get_project_by_key;
+use async_ssh2_tokio::client::Client;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+#[tauri::command]
+pub async fn open_remote_file(app: AppHandle, key: String, path: String) -> Result<String, String> {
+    let project = get_project_by_key(&key)?;
+    let connection = Arc::clone(&project.main_connection);
+
+    // Reuse the existing temp copy if this file is already open
+    {
+        let opened_files = project.opened_files.lock().await;
+        if let Some(opened) = opened_files.iter().find(|f| f.remote_path == path) {
+            let local_path = opened.local_path.clone();
+            drop(opened_files);
+            open_local(&local_path)?;
+            return Ok(local_path.to_string_lossy().to_string());
+        }
+    }
+
+    let file_name = Path::new(&path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| format!("Not a file path: {}", path))?;
+
+    // Each file gets its own directory so the local name matches the remote one
+    let temp_dir = std::env::temp_dir()
+        .join("remote-explorer")
+        .join(&key)
+        .join(Uuid::new_v4().to_string());
+    std::fs::create_dir_all(&temp_dir)
+        .map_err(|e| format!("Failed to create temp directory: {}", e))?;
+    let local_path = temp_dir.join(&file_name);
+
+    let downloaded = match remote_stamp(&connection, &path).await {
+        Ok(stamp) => connection
+            .download_file(path.clone(), &local_path)
+            .await
+            .map(|_| stamp)
+            .map_err(|e| format!("Failed to download file: {}", e)),
+        Err(e) => Err(e),
+    };
+    let stamp = match downloaded {
+        Ok(stamp) => stamp,
+        Err(e) => {
+            let _ = std::fs::remove_dir_all(&temp_dir);
+            return Err(e);
+        }
+    };
+
+    let remote_stamp = Arc::new(Mutex::new(stamp));
+    let unsynced = Arc::new(Mutex::new(false));
+    let watcher = tauri::async_runtime::spawn(watch_local_copy(
+        app,
+        key.clone(),
+        Arc::clone(&connection),
+        path.clone(),
+        local_path.clone(),
+        Arc::clone(&remote_stamp),
+        Arc::clone(&unsynced),
+    ));
+
+    project.opened_files.lock().await.push(OpenedFile {
+        remote_path: path,
+        local_path: local_path.clone(),
+        temp_dir,
+        remote_stamp,
+        unsynced,
+        watcher,
+    });
+
+    open_local(&local_path)?;
+    Ok(local_path.to_string_lossy().to_string())
+}
+
+/// Uploads the local copy even if the remote file changed since it was downloaded.
+#[tauri::command]
+pub async fn force_upload_opened_file(app: AppHandle, key: String, path: String) -> Result<(), String> {
+    let project = get_project_by_key(&key)?;
+
+    let (local_path, remote_stamp, unsynced) = {
+        let opened_files = project.opened_files.lock().await;
+        let opened = opened_files
+            .iter()
+            .find(|f| f.remote_path == path)
+            .ok_or_else(|| "File is not open".to_string())?;
+        (
+            opened.local_path.clone(),
+            Arc::clone(&opened.remote_stamp),
+            Arc::clone(&opened.unsynced),
+        )
+    };
+
+    upload(&project.main_connection, &path, &local_path, &remote_stamp).await?;
+    *unsynced.lock().await = false;
+    let _ = app.emit_to(
+        project_window_label(&key).as_str(),
+        "remote-file-synced",
+        RemoteFileEvent { key, path, error: None },
+    );
+    Ok(())
+}
+
+/// Stops watching a file and deletes its temp copy. A copy with local edits that never
+/// reached the server is only deleted with `discard_changes` set.
+#[tauri::command]
+pub async fn close_opened_file(
+    key: String,
+    path: String,
+    discard_changes: Option<bool>,
+) -> Result<(), String> {
+    let project = get_project_by_key(&key)?;
+
+    let mut opened_files = project.opened_files.lock().await;
+    let index = opened_files
+        .iter()
+        .position(|f| f.remote_path == path)
+        .ok_or_else(|| "File is not open".to_string())?;
+
+    if *opened_files[index].unsynced.lock().await && !discard_changes.unwrap_or(false) {
+        return Err("File has local changes that were not uploaded".to_string());
+    }
+
+    discard(opened_files.remove(index));
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn list_opened_files(key: String) -> Result<Vec<String>, String> {
+    let project = get_project_by_key(&key)?;
+
+    let opened_files = project.opened_files.lock().await;
+    Ok(opened_files.iter().map(|f| f.remote_path.clone()).collect())
+}
+
+/// Stops all watchers of a project and removes their temp copies. Copies with edits
+/// that never reached the server stay in the temp directory so no work is lost.
+pub async fn cleanup_opened_files(project: &Project) {
+    let mut opened_files = project.opened_files.lock().await;
+    for opened in opened_files.drain(..) {
+        if *opened.unsynced.lock().await {
+            opened.watcher.abort();
+        } else {
+            discard(opened);
+        }
+    }
+}
+
+fn discard(opened: OpenedFile) {
+    opened.watcher.abort();
+    let _ = std::fs::remove_dir_all(&opened.temp_dir);
+}
+
+fn open_local(local_path: &Path) -> Result<(), String> {
+    tauri_plugin_opener::open_path(local_path, None::<&str>)
+        .map_err(|e| format!("Failed to open file: {}", e))
+}
+
+async fn remote_stamp(connection: &Arc<Client>, path: &str) -> Result<RemoteStamp, String> {
+    let result = connection
+        .execute(&format!("stat -L --format='%Y|%s' {}", shell_quote(path)))
+        .await
+        .map_err(|e| format!("Failed to stat file: {}", e))?;
+
+    if result.exit_status != 0 {
+        return Err(format!("Failed to stat file: {}", result.stderr.trim()));
+    }
+
+    let mut parts = result.stdout.trim().split('|');
+    let modified = parts.next().and_then(|p| p.parse().ok());
+    let size = parts.next().and_then(|p| p.parse().ok());
+
+    match (modified, size) {
+        (Some(modified), Some(size)) => Ok(RemoteStamp { modified, size }),
+        _ => Err("Invalid stat output".to_string()),
+    }
+}
+
+// Refuses to overwrite the remote file if it changed since the last download or upload
+async fn upload_if_unchanged(
+    connection: &Arc<Client>,
+    path: &str,
+    local_path: &Path,
+    known_stamp: &Arc<Mutex<RemoteStamp>>,
+) -> Result<bool, String> {
+    let current = remote_stamp(connection, path).await?;
+    if current != *known_stamp.lock().await {
+        return Ok(false);
+    }
+
+    upload(connection, path, local_path, known_stamp).await?;
+    Ok(true)
+}
+
+async fn upload(
+    connection: &Arc<Client>,
+    path: &str,
+    local_path: &Path,
+    known_stamp: &Arc<Mutex<RemoteStamp>>,
+) -> Result<(), String> {
+    connection
+        .upload_file(local_path, path.to_string())
+        .await
+        .map_err(|e| format!("Failed to upload file: {}", e))?;
+
+    *known_stamp.lock().await = remote_stamp(connection, path).await?;
+    Ok(())
+}
+
+fn local_modified(local_path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(local_path).and_then(|m| m.modified()).ok()
+}
+
+async fn watch_local_copy(
+    app: AppHandle,
+    key: String,
+    connection: Arc<Client>,
+    path: String,
+    local_path: PathBuf,
+    remote_stamp: Arc<Mutex<RemoteStamp>>,
+    unsynced: Arc<Mutex<bool>>,
+) {
+    let mut last_seen = local_modified(&local_path);
+
+    loop {
+        tokio::time::sleep(WATCH_INTERVAL).await;
+
+        let modified = local_modified(&local_path);
+        if modified.is_none() || modified == last_seen {
+            continue;
+        }
+        last_seen = modified;
+
+        let uploaded = upload_if_unchanged(&connection, &path, &local_path, &remote_stamp).await;
+        *unsynced.lock().await = !matches!(uploaded, Ok(true));
+
+        match uploaded {
+            Ok(true) => {
+                let _ = app.emit_to(
+                    project_window_label(&key).as_str(),
+                    "remote-file-synced",
+                    RemoteFileEvent { key: key.clone(), path: path.clone(), error: None },
+                );
+            }
+            Ok(false) => {
+                let _ = app.emit_to(
+                    project_window_label(&key).as_str(),
+                    "remote-file-conflict",
+                    RemoteFileEvent { key: key.clone(), path: path.clone(), error: None },
+                );
+            }
+            Err(e) => {
+                let _ = app.emit_to(
+                    project_window_label(&key).as_str(),
+                    "remote-file-sync-failed",
+                    RemoteFileEvent { key: key.clone(), path: path.clone(), error: Some(e) },
+                );
+            }
+        }
+    }
+}
@@ -1,3 +1,11 @@
+pub mod bookmarks;
+pub mod broadcast;
 pub mod connection;
+pub mod exec;
 pub mod filesystem;
+pub mod fleet;
+pub mod history;
+pub mod local_edit;
+pub mod settings;
+pub mod snippets;
 pub mod terminal;
\ No newline at end of file
@@ -0,0 +1,21 @@
+use crate::models::ProjectSettings;
+use crate::persistence::{load, project_entry_key, save};
+use tauri::AppHandle;
+
+pub fn load_settings(app: &AppHandle, key: &str) -> Result<ProjectSettings, String> {
+    load(app, &project_entry_key(key, "settings"))
+}
+
+#[tauri::command]
+pub fn get_project_settings(app: AppHandle, key: String) -> Result<ProjectSettings, String> {
+    load_settings(&app, &key)
+}
+
+#[tauri::command]
+pub fn set_project_settings(
+    app: AppHandle,
+    key: String,
+    settings: ProjectSettings,
+) -> Result<(), String> {
+    save(&app, &project_entry_key(&key, "settings"), &settings)
+}
@@ -0,0 +1,267 @@
+use crate::commands::terminal::execute_terminal_command;
+use crate::models::{CommandOutput, Project, Snippet, SnippetInfo};
+use crate::paths::shell_quote;
+use crate::persistence::{backend_key, contains, load, project_entry_key, save, update};
+use crate::state::get_project_by_key;
+use std::collections::HashMap;
+use tauri::AppHandle;
+use uuid::Uuid;
+
+// Earlier versions kept global snippets under a bare key the frontend could also use
+const LEGACY_GLOBAL_SNIPPETS_KEY: &str = "snippets";
+
+// Filled in by the backend; every other placeholder is prompted for
+const BUILTIN_PLACEHOLDERS: &[&str] = &["path", "selected_files", "project", "host", "user"];
+
+// Project snippets live with the project, global ones under a key of their own
+fn snippets_key(key: Option<&str>) -> String {
+    match key {
+        Some(key) => project_entry_key(key, "snippets"),
+        None => backend_key("snippets"),
+    }
+}
+
+// Only taken over when the old key really holds snippets; anything else there is not ours
+fn migrate_global_snippets(app: &AppHandle) -> Result<(), String> {
+    let global_key = snippets_key(None);
+    if contains(app, &global_key)? {
+        return Ok(());
+    }
+
+    match load::<Vec<Snippet>>(app, LEGACY_GLOBAL_SNIPPETS_KEY) {
+        Ok(snippets) if !snippets.is_empty() => save(app, &global_key, &snippets),
+        _ => Ok(()),
+    }
+}
+
+fn load_snippets(app: &AppHandle, key: Option<&str>) -> Result<Vec<Snippet>, String> {
+    if key.is_none() {
+        migrate_global_snippets(app)?;
+    }
+    load(app, &snippets_key(key))
+}
+
+fn update_snippets<R>(
+    app: &AppHandle,
+    key: Option<&str>,
+    update_with: impl FnOnce(&mut Vec<Snippet>) -> Result<R, String>,
+) -> Result<R, String> {
+    if key.is_none() {
+        migrate_global_snippets(app)?;
+    }
+    update(app, &snippets_key(key), update_with)
+}
+
+fn snippet_info(snippet: Snippet, scope: &str) -> SnippetInfo {
+    SnippetInfo {
+        placeholders: placeholders(&snippet.command),
+        id: snippet.id,
+        name: snippet.name,
+        command: snippet.command,
+        description: snippet.description,
+        scope: scope.to_string(),
+    }
+}
+
+// Names between `{{` and `}}`, trimmed, paired with the byte range of the whole placeholder
+fn placeholder_spans(template: &str) -> Vec<(usize, usize, &str)> {
+    let mut spans = Vec::new();
+    let mut position = 0;
+
+    while let Some(start) = template[position..].find("{{").map(|i| position + i) {
+        let Some(end) = template[start + 2..].find("}}").map(|i| start + 2 + i) else {
+            break;
+        };
+
+        spans.push((start, end + 2, template[start + 2..end].trim()));
+        position = end + 2;
+    }
+
+    spans
+}
+
+/// Placeholders the user has to provide a value for, in order of first use.
+fn placeholders(template: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+
+    for (_, _, name) in placeholder_spans(template) {
+        if !BUILTIN_PLACEHOLDERS.contains(&name) && !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+    }
+
+    names
+}
+
+/// Replaces every placeholder with its value. Built-in values are shell-quoted,
+/// prompted values are inserted as typed so they can carry options.
+fn render(
+    template: &str,
+    builtins: &HashMap<&str, String>,
+    values: &HashMap<String, String>,
+) -> Result<String, String> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut position = 0;
+
+    for (start, end, name) in placeholder_spans(template) {
+        let value = builtins
+            .get(name)
+            .or_else(|| values.get(name))
+            .ok_or_else(|| format!("Missing value for {{{{{}}}}}", name))?;
+
+        rendered.push_str(&template[position..start]);
+        rendered.push_str(value);
+        position = end;
+    }
+    rendered.push_str(&template[position..]);
+
+    Ok(rendered)
+}
+
+fn builtin_values(project: &Project, path: &str, selected_files: &[String]) -> HashMap<&'static str, String> {
+    HashMap::from([
+        ("path", shell_quote(path)),
+        (
+            "selected_files",
+            selected_files
+                .iter()
+                .map(|f| shell_quote(f))
+                .collect::<Vec<_>>()
+                .join(" "),
+        ),
+        ("project", shell_quote(&project.name)),
+        ("host", shell_quote(&project.host)),
+        ("user", shell_quote(&project.user)),
+    ])
+}
+
+/// Lists global snippets, followed by the project's own when `key` is given.
+#[tauri::command]
+pub fn list_snippets(app: AppHandle, key: Option<String>) -> Result<Vec<SnippetInfo>, String> {
+    let mut snippets: Vec<SnippetInfo> = load_snippets(&app, None)?
+        .into_iter()
+        .map(|s| snippet_info(s, "global"))
+        .collect();
+
+    if let Some(key) = key.as_deref() {
+        snippets.extend(
+            load_snippets(&app, Some(key))?
+                .into_iter()
+                .map(|s| snippet_info(s, "project")),
+        );
+    }
+
+    Ok(snippets)
+}
+
+/// Adds a snippet, or replaces the one with the same id. Saved globally unless `key` is given.
+#[tauri::command]
+pub fn save_snippet(
+    app: AppHandle,
+    key: Option<String>,
+    id: Option<String>,
+    name: String,
+    command: String,
+    description: Option<String>,
+) -> Result<SnippetInfo, String> {
+    if name.trim().is_empty() {
+        return Err("Snippet name cannot be empty".to_string());
+    }
+    if command.trim().is_empty() {
+        return Err("Snippet command cannot be empty".to_string());
+    }
+
+    let key = key.as_deref();
+
+    let snippet = Snippet {
+        id: id.unwrap_or_else(|| Uuid::new_v4().to_string()),
+        name: name.trim().to_string(),
+        command,
+        description: description.filter(|d| !d.trim().is_empty()),
+    };
+
+    update_snippets(&app, key, |snippets| {
+        match snippets.iter_mut().find(|s| s.id == snippet.id) {
+            Some(existing) => *existing = snippet.clone(),
+            None => snippets.push(snippet.clone()),
+        }
+        Ok(())
+    })?;
+
+    Ok(snippet_info(snippet, if key.is_some() { "project" } else { "global" }))
+}
+
+#[tauri::command]
+pub fn delete_snippet(app: AppHandle, key: Option<String>, id: String) -> Result<(), String> {
+    update_snippets(&app, key.as_deref(), |snippets| {
+        let initial_len = snippets.len();
+        snippets.retain(|s| s.id != id);
+
+        if snippets.len() == initial_len {
+            return Err("Snippet not found".to_string());
+        }
+        Ok(())
+    })
+}
+
+/// Renders a snippet and types it into `terminal_id`, or runs it once on the project
+/// connection when no terminal is given. `path` defaults to the terminal's current
+/// directory; `values` holds the prompted placeholders.
+#[tauri::command]
+pub async fn run_snippet(
+    app: AppHandle,
+    key: String,
+    snippet_id: String,
+    terminal_id: Option<String>,
+    path: Option<String>,
+    selected_files: Option<Vec<String>>,
+    values: Option<HashMap<String, String>>,
+) -> Result<Option<CommandOutput>, String> {
+    let project = get_project_by_key(&key)?;
+
+    // Project snippets shadow global ones with the same id
+    let snippet = load_snippets(&app, Some(&key))?
+        .into_iter()
+        .chain(load_snippets(&app, None)?)
+        .find(|s| s.id == snippet_id)
+        .ok_or_else(|| "Snippet not found".to_string())?;
+
+    let terminal_cwd = match &terminal_id {
+        Some(terminal_id) => {
+            let terminals = project.terminal_connections.lock().await;
+            let terminal = terminals
+                .iter()
+                .find(|t| &t.id == terminal_id)
+                .ok_or_else(|| "Terminal not found".to_string())?;
+
+            let cwd = terminal.commands.lock().await.cwd.clone();
+            Some(cwd.unwrap_or_else(|| terminal.path.clone()))
+        }
+        None => None,
+    };
+
+    let path = path
+        .filter(|p| !p.trim().is_empty())
+        .or(terminal_cwd)
+        .unwrap_or_else(|| ".".to_string());
+
+    let builtins = builtin_values(&project, &path, &selected_files.unwrap_or_default());
+    let command = render(&snippet.command, &builtins, &values.unwrap_or_default())?;
+
+    if let Some(terminal_id) = terminal_id {
+        execute_terminal_command(app, key, terminal_id, command).await?;
+        return Ok(None);
+    }
+
+    let result = project
+        .main_connection
+        .execute(&format!("cd {} && {}", shell_quote(&path), command))
+        .await
+        .map_err(|e| format!("Failed to run snippet: {}", e))?;
+
+    Ok(Some(CommandOutput {
+        stdout: result.stdout,
+        stderr: result.stderr,
+        exit_status: result.exit_status,
+    }))
+}
@@ -1,33 +1,70 @@
-use crate::auth::build_auth_method;
-use crate::models::{TerminalConnection, TerminalExecution};
-use crate::ssh::{connect_to_ssh, parse_host_port};
-use crate::state::get_project_by_key;
+use crate::commands::connection::project_window_label;
+use crate::commands::history::record_command;
+use crate::commands::settings::load_settings;
+use crate::models::{
+    Project, TerminalCommandEvent, TerminalConnection, TerminalContent, TerminalControl, TerminalCwdEvent,
+    TerminalExecution, TerminalExit, TerminalExitEvent, TerminalInfo, TerminalLaunch,
+    TerminalOptions, TerminalOutputEvent, TerminalSearchResult,
+};
+use crate::paths::shell_quote;
+use crate::ssh::ConnectionPool;
+use crate::state::{get_project_by_key, remove_broadcast_members};
+use crate::terminal::ansi::strip_ansi;
+use crate::terminal::buffer::{ScrollbackBuffer, DEFAULT_SCROLLBACK_BYTES};
+use crate::terminal::logging::{TerminalLogger, DEFAULT_LOG_MAX_BYTES, DEFAULT_LOG_MAX_FILES};
+use crate::terminal::multiplexer::{
+    list_sessions, validate_session_name, Multiplexer, PersistentSession, RemoteSession,
+};
+use crate::terminal::recording::{read_recording, Recorder, Recording};
+use crate::terminal::screen::{Screen, ScreenSnapshot};
+use crate::terminal::search::{build_pattern, search_output};
+use crate::terminal::session::{parse_signal, run_pty_session, PtyOptions, MAX_COLS, MAX_ROWS};
+use crate::terminal::shell::{detect_shells, launch_command, ShellInfo, ShellSpec};
+use crate::terminal::shell_integration::{
+    now_millis, CommandRecord, CommandTracker, OscScanner, ShellEvent,
+};
+use crate::terminal::sudo::{redact, SudoResponder};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
 use tokio::sync::{mpsc, Mutex};
 use uuid::Uuid;
 
+const FLUSH_INTERVAL: Duration = Duration::from_secs(2);
+const DEFAULT_SEARCH_RESULTS: usize = 1000;
+
 #[tauri::command]
 pub async fn create_terminal(
+    app: AppHandle,
     key: String,
     path: String,
+    options: Option<TerminalOptions>,
 ) -> Result<String, String> {
     let project = get_project_by_key(&key)?;
+    let options = options.unwrap_or_default();
+    let pty = PtyOptions::new(options.term, options.cols, options.rows);
+    let screen = Screen::new(pty.cols, pty.rows);
+    let scrollback =
+        ScrollbackBuffer::new(options.scrollback_bytes.unwrap_or(DEFAULT_SCROLLBACK_BYTES));
+    let settings = load_settings(&app, &key)?;
 
-    let host_port = parse_host_port(&project.host);
-    let auth = build_auth_method(
-        &project.auth_method,
-        project.password.as_deref(),
-        project.key_file.as_deref(),
-        project.public_key_file.as_deref(),
-    )?;
-
-    let user = project.user.clone();
-
-    let client = connect_to_ssh(&host_port.hostname, host_port.port, &user, auth, 10).await?;
+    let mut environment = settings.environment;
+    environment.extend(options.environment);
+    let shell_spec = ShellSpec {
+        shell: options.shell.or(settings.shell).filter(|s| !s.trim().is_empty()),
+        login: options.login.unwrap_or(settings.login_shell),
+        environment,
+    };
+    let init_command = options
+        .init_command
+        .or(settings.init_command)
+        .filter(|c| !c.trim().is_empty());
 
     // Verify the path exists
-    let cd_result = client
-        .execute(&format!("cd {} && pwd", path))
+    let cd_result = project
+        .main_connection
+        .execute(&format!("cd {} && pwd", shell_quote(&path)))
         .await
         .map_err(|e| format!("Failed to change directory: {}", e))?;
 
@@ -35,112 +72,356 @@ pub async fn create_terminal(
         return Err(format!("Failed to navigate to path: {}", path));
     }
 
+    let shell_command = launch_command(&project.main_connection, &shell_spec, &path).await?;
+
     let terminal_id = Uuid::new_v4().to_string();
 
+    // Naming a session implies tmux unless the project prefers another multiplexer
+    let session_name = options.session_name;
+    let multiplexer = match (options.multiplexer.or(settings.multiplexer), &session_name) {
+        (Some(name), _) => Some(Multiplexer::parse(&name)?),
+        (None, Some(_)) => Some(Multiplexer::Tmux),
+        (None, None) => None,
+    };
+
+    let session = match multiplexer {
+        Some(multiplexer) => {
+            let name = session_name
+                .unwrap_or_else(|| format!("remote-explorer-{}", &terminal_id[..8]));
+            validate_session_name(&name)?;
+            Some(PersistentSession { multiplexer, name })
+        }
+        None => None,
+    };
+
+    let shell_command = match &session {
+        Some(session) => session.multiplexer.attach_command(&session.name, &shell_command),
+        None => shell_command,
+    };
+
     let terminal_connection = TerminalConnection {
         id: terminal_id.clone(),
-        connection: Arc::new(client),
-        content_lines: Arc::new(Mutex::new(String::new())), // Changed to String
+        scrollback: Arc::new(Mutex::new(scrollback)),
         current_executions: Arc::new(Mutex::new(Vec::new())),
         path: path.clone(),
+        sudo: (settings.auto_sudo && project.password.is_some())
+            .then(|| Arc::new(Mutex::new(SudoResponder::default()))),
+        commands: Arc::new(Mutex::new(CommandTracker::default())),
+        session,
+        exit: Arc::new(Mutex::new(None)),
+        launch: Arc::new(Mutex::new(TerminalLaunch {
+            shell_command,
+            pty,
+            init_command,
+        })),
+        recorder: Arc::new(Mutex::new(None)),
+        logger: Arc::new(Mutex::new(None)),
+        screen: Arc::new(Mutex::new(screen)),
+    };
+
+    start_shell(app, Arc::clone(&project), &terminal_connection).await?;
+
+    // Add terminal connection to the project
+    {
+        let mut terminals = project
+            .terminal_connections
+            .lock()
+            .await;
+
+        terminals.push(terminal_connection);
+    }
+
+    Ok(terminal_id)
+}
+
+// Opens a channel for the terminal's shell from the project's connection pool and runs it
+// in the background; it reports its exit through `terminal.exit`
+async fn start_shell(
+    app: AppHandle,
+    project: Arc<Project>,
+    terminal: &TerminalConnection,
+) -> Result<(), String> {
+    let launch = terminal.launch.lock().await.clone();
+
+    let scrollback = Arc::clone(&terminal.scrollback);
+    let sudo = terminal.sudo.clone();
+    let commands = Arc::clone(&terminal.commands);
+    let terminal_exit = Arc::clone(&terminal.exit);
+    let recorder = Arc::clone(&terminal.recorder);
+    let logger = Arc::clone(&terminal.logger);
+    let screen = Arc::clone(&terminal.screen);
+    let current_executions = Arc::clone(&terminal.current_executions);
+    let terminal_id = terminal.id.clone();
+
+    let (stdout_tx, mut stdout_rx) = mpsc::channel::<Vec<u8>>(100);
+    let (stdin_tx, stdin_rx) = mpsc::channel::<Vec<u8>>(100);
+    let (control_tx, control_rx) = mpsc::channel::<TerminalControl>(16);
+
+    // Registered before the channel is opened, so a concurrent restart sees the shell as
+    // running and input sent meanwhile waits in the queue
+    {
+        let mut executions = current_executions.lock().await;
+        if !executions.is_empty() {
+            return Err("Terminal is still running".to_string());
+        }
+        executions.push(TerminalExecution {
+            stdin_tx: stdin_tx.clone(),
+            control_tx,
+            command: launch.shell_command.clone(),
+        });
+    }
+
+    let opened = ConnectionPool::open_channel(&project.terminal_pool, &project).await;
+    let (connection, channel) = match opened {
+        Ok(opened) => opened,
+        Err(e) => {
+            current_executions.lock().await.clear();
+            return Err(e);
+        }
     };
 
-    // Start an interactive shell session
-    let connection = Arc::clone(&terminal_connection.connection);
-    let content_lines = Arc::clone(&terminal_connection.content_lines);
-    let current_executions = Arc::clone(&terminal_connection.current_executions);
-    let project_clone = project.clone();
-    let terminal_id_clone = terminal_id.clone();
     tauri::async_runtime::spawn(async move {
-        let (stdout_tx, mut stdout_rx) = mpsc::channel::<Vec<u8>>(100);
-        let (stdin_tx, stdin_rx) = mpsc::channel::<Vec<u8>>(100);
-
-        // Store the stdin sender for this execution
-        {
-            let mut executions = current_executions.lock().await;
-            executions.push(TerminalExecution {
-                stdin_tx: stdin_tx.clone(),
-                command: "bash".to_string(),
-            });
+        // Queued until the shell reads its input, so it runs right after startup
+        if let Some(init_command) = &launch.init_command {
+            let _ = stdin_tx.send(format!("{}\n", init_command).into_bytes()).await;
         }
 
-        let content_lines_clone = Arc::clone(&content_lines);
+        let scrollback_clone = Arc::clone(&scrollback);
+        let recorder_clone = Arc::clone(&recorder);
+        let logger_clone = Arc::clone(&logger);
+        let screen_clone = Arc::clone(&screen);
         let current_executions_clone = Arc::clone(&current_executions);
 
-        let project_clone = project_clone.clone();
-        let terminal_id_clone = terminal_id_clone.clone();
+        let password_tx = stdin_tx.clone();
+        let output_app = app.clone();
+        let project_clone = Arc::clone(&project);
+        let terminal_id_clone = terminal_id.clone();
         // Spawn task to collect stdout as continuous stream
         tauri::async_runtime::spawn(async move {
-            while let Some(data) = stdout_rx.recv().await {
-                let text = String::from_utf8_lossy(&data).trim().to_string();
+            let mut scanner = OscScanner::default();
 
-                if let Some(password_clone) = project_clone.password.clone() {
-                    if &text == &format!("[sudo] password for {}:", project_clone.user) {
-                        let _ = execute_terminal_command(project_clone.key.clone(), terminal_id_clone.clone(), password_clone).await;
-                        continue; // Skip sudo password prompts
+            while let Some(data) = stdout_rx.recv().await {
+                let data = match (&sudo, &project_clone.password) {
+                    (Some(sudo), Some(password)) => {
+                        if sudo.lock().await.observe(&data) {
+                            // Goes straight to stdin so it never shows up in history or recordings
+                            let _ = password_tx.send(format!("{}\n", password).into_bytes()).await;
+                        }
+                        redact(&data, password)
                     }
-                }
+                    _ => data,
+                };
 
-                if (text.ends_with("$") || text.ends_with(":")) && text.contains(project_clone.user.as_str()) && !text.starts_with("[sudo] ") {
-                    continue; // Skip prompt lines
-                }
+                screen_clone.lock().await.feed(&data);
+
+                let base_offset = append_output(
+                    &output_app,
+                    &project_clone.key,
+                    &terminal_id_clone,
+                    &scrollback_clone,
+                    &recorder_clone,
+                    &logger_clone,
+                    &data,
+                )
+                .await;
 
-                let mut content = content_lines_clone.lock().await;
-                content.push_str(&text);
-                
-                // Keep only last 100KB to prevent memory issues
-                if content.len() > 100_000 {
-                    let trim_from = content.len() - 100_000;
-                    *content = content[trim_from..].to_string();
+                for (position, event) in scanner.feed(&data) {
+                    track_shell_event(
+                        &output_app,
+                        &project_clone.key,
+                        &terminal_id_clone,
+                        &scrollback_clone,
+                        &commands,
+                        event,
+                        base_offset + position as u64,
+                    )
+                    .await;
                 }
             }
         });
 
-        // Start an interactive bash shell with PTY
-        let shell_command = format!("cd {} && exec bash", path);
-        let result = connection
-            .execute_io(
-                &shell_command,
-                stdout_tx,
-                None, // No stderr channel - PTY sends everything to stdout
-                Some(stdin_rx),
-                true, // request_pty = true for interactive shell
-                Some(0),
-            )
-            .await;
+        // Start the interactive shell with PTY
+        let result = run_pty_session(
+            channel,
+            &launch.shell_command,
+            &launch.pty,
+            stdout_tx,
+            stdin_rx,
+            control_rx,
+        )
+        .await;
 
         // When shell exits, remove from executions
-        let mut executions = current_executions_clone.lock().await;
-        executions.clear();
+        current_executions_clone.lock().await.clear();
+        project.terminal_pool.lock().await.release(&connection).await;
 
-        if let Err(e) = result {
-            let mut content = content_lines.lock().await;
-            content.push_str(&format!("\r\nShell exited with error: {}\r\n", e));
-        }
+        let exit = match result {
+            Ok(exit) => exit,
+            Err(e) => {
+                let message = format!("\r\nShell exited with error: {}\r\n", e);
+                screen.lock().await.feed(message.as_bytes());
+                append_output(
+                    &app,
+                    &project.key,
+                    &terminal_id,
+                    &scrollback,
+                    &recorder,
+                    &logger,
+                    message.as_bytes(),
+                )
+                .await;
+
+                TerminalExit {
+                    error: Some(e),
+                    ..TerminalExit::default()
+                }
+            }
+        };
+
+        *terminal_exit.lock().await = Some(exit.clone());
+        let _ = app.emit_to(
+            project_window_label(&project.key).as_str(),
+            "terminal-exited",
+            TerminalExitEvent {
+                key: project.key.clone(),
+                terminal_id,
+                exit,
+            },
+        );
     });
 
-    // Add terminal connection to the project
+    Ok(())
+}
+
+// Stores raw output in the scrollback, records and logs it when enabled and pushes the
+// completed text to the window. Returns the scrollback offset of the first stored byte.
+async fn append_output(
+    app: &AppHandle,
+    key: &str,
+    terminal_id: &str,
+    scrollback: &Mutex<ScrollbackBuffer>,
+    recorder: &Mutex<Option<Recorder>>,
+    logger: &Mutex<Option<TerminalLogger>>,
+    bytes: &[u8],
+) -> u64 {
+    let (base_offset, chunk) = {
+        let mut scrollback = scrollback.lock().await;
+        let base_offset = scrollback.end_offset();
+        (base_offset, scrollback.push(bytes))
+    };
+    if chunk.data.is_empty() {
+        return base_offset;
+    }
+
     {
-        let mut terminals = project
-            .terminal_connections
-            .lock()
-            .await;
+        let mut recorder = recorder.lock().await;
+        if let Some(active) = recorder.as_mut() {
+            // A recording that can no longer be written is stopped rather than retried
+            if active.output(&chunk.data).is_err() {
+                *recorder = None;
+            }
+        }
+    }
 
-        terminals.push(terminal_connection);
+    {
+        let mut logger = logger.lock().await;
+        if let Some(active) = logger.as_mut() {
+            if active.write(&chunk.data).is_err() {
+                *logger = None;
+            }
+        }
     }
 
-    Ok(terminal_id)
+    // Only the project's own window gets its output
+    let _ = app.emit_to(
+        project_window_label(key).as_str(),
+        "terminal-output",
+        TerminalOutputEvent {
+            key: key.to_string(),
+            terminal_id: terminal_id.to_string(),
+            data: chunk.data,
+            offset: chunk.offset,
+            next_offset: chunk.next_offset,
+        },
+    );
+
+    base_offset
+}
+
+// Typed input is recorded with the project password masked, in case it was typed at a prompt
+async fn record_input(recorder: &Mutex<Option<Recorder>>, password: Option<&str>, input: &str) {
+    let mut recorder = recorder.lock().await;
+    let Some(active) = recorder.as_mut() else {
+        return;
+    };
+
+    let input = match password {
+        Some(password) => String::from_utf8_lossy(&redact(input.as_bytes(), password)).to_string(),
+        None => input.to_string(),
+    };
+
+    if active.input(&input).is_err() {
+        *recorder = None;
+    }
+}
+
+async fn track_shell_event(
+    app: &AppHandle,
+    key: &str,
+    terminal_id: &str,
+    scrollback: &Mutex<ScrollbackBuffer>,
+    commands: &Mutex<CommandTracker>,
+    event: ShellEvent,
+    offset: u64,
+) {
+    let mut tracker = commands.lock().await;
+
+    if let ShellEvent::WorkingDirectory(cwd) = &event {
+        if tracker.cwd.as_ref() != Some(cwd) {
+            let _ = app.emit_to(
+                project_window_label(key).as_str(),
+                "terminal-cwd-changed",
+                TerminalCwdEvent {
+                    key: key.to_string(),
+                    terminal_id: terminal_id.to_string(),
+                    cwd: cwd.clone(),
+                },
+            );
+        }
+    }
+
+    // The command line is whatever the shell echoed between the prompt and execution
+    let command = match (&event, tracker.input_offset()) {
+        (ShellEvent::CommandExecuted, Some(input_offset)) => {
+            let typed = scrollback.lock().await.read_range(input_offset, offset);
+            Some(strip_ansi(&typed).trim().to_string())
+        }
+        _ => None,
+    };
+
+    if let Some(record) = tracker.apply(event, offset, command) {
+        let _ = app.emit_to(
+            project_window_label(key).as_str(),
+            "terminal-command-finished",
+            TerminalCommandEvent {
+                key: key.to_string(),
+                terminal_id: terminal_id.to_string(),
+                record,
+            },
+        );
+    }
 }
 
 #[tauri::command]
 pub async fn execute_terminal_command(
+    app: AppHandle,
     key: String,
     terminal_id: String,
     command: String,
 ) -> Result<(), String> {
     let project = get_project_by_key(&key)?;
 
-    let current_executions = {
+    let (current_executions, sudo, recorder) = {
         let terminals = project
             .terminal_connections
             .lock()
@@ -151,7 +432,11 @@ pub async fn execute_terminal_command(
             .find(|t| t.id == terminal_id)
             .ok_or_else(|| "Terminal not found".to_string())?;
 
-        Arc::clone(&terminal.current_executions)
+        (
+            Arc::clone(&terminal.current_executions),
+            terminal.sudo.clone(),
+            Arc::clone(&terminal.recorder),
+        )
     };
 
     let executions = current_executions.lock().await;
@@ -159,7 +444,13 @@ pub async fn execute_terminal_command(
     // Get the bash execution
     if let Some(bash_exec) = executions.first() {
         // Send command to stdin with newline
+        if let Some(sudo) = &sudo {
+            sudo.lock().await.note_command(&command);
+        }
+
         let command_with_newline = format!("{}\n", command);
+        record_input(&recorder, project.password.as_deref(), &command_with_newline).await;
+        let _ = record_command(&app, &key, &command, project.password.as_deref());
         bash_exec
             .stdin_tx
             .send(command_with_newline.into_bytes())
@@ -168,7 +459,7 @@ pub async fn execute_terminal_command(
         
         Ok(())
     } else {
-        Err("No active bash session".to_string())
+        Err("No active shell session".to_string())
     }
 }
 
@@ -180,7 +471,7 @@ pub async fn send_terminal_input(
 ) -> Result<(), String> {
     let project = get_project_by_key(&key)?;
 
-    let current_executions = {
+    let (current_executions, sudo, recorder) = {
         let terminals = project
             .terminal_connections
             .lock()
@@ -191,13 +482,23 @@ pub async fn send_terminal_input(
             .find(|t| t.id == terminal_id)
             .ok_or_else(|| "Terminal not found".to_string())?;
 
-        Arc::clone(&terminal.current_executions)
+        (
+            Arc::clone(&terminal.current_executions),
+            terminal.sudo.clone(),
+            Arc::clone(&terminal.recorder),
+        )
     };
 
     let executions = current_executions.lock().await;
 
     // Get the bash execution
     if let Some(bash_exec) = executions.first() {
+        if let Some(sudo) = &sudo {
+            sudo.lock().await.note_input(&input);
+        }
+
+        record_input(&recorder, project.password.as_deref(), &input).await;
+
         // Send raw input (useful for Ctrl+C, arrow keys, etc.)
         bash_exec
             .stdin_tx
@@ -207,7 +508,7 @@ pub async fn send_terminal_input(
         
         Ok(())
     } else {
-        Err("No active bash session".to_string())
+        Err("No active shell session".to_string())
     }
 }
 
@@ -215,33 +516,77 @@ pub async fn send_terminal_input(
 pub async fn close_terminal(key: String, terminal_id: String) -> Result<(), String> {
     let project = get_project_by_key(&key)?;
 
-    let mut terminals = project
-        .terminal_connections
-        .lock()
-        .await;
+    let terminal = {
+        let mut terminals = project
+            .terminal_connections
+            .lock()
+            .await;
+
+        let index = terminals
+            .iter()
+            .position(|t| t.id == terminal_id)
+            .ok_or_else(|| "Terminal not found".to_string())?;
 
-    let initial_len = terminals.len();
+        terminals.remove(index)
+    };
 
-    // Before removing, try to close the shell gracefully
-    if let Some(terminal) = terminals.iter().find(|t| t.id == terminal_id) {
-        let executions = terminal.current_executions.lock().await;
-        if let Some(bash_exec) = executions.first() {
+    remove_broadcast_members(&key, Some(&terminal_id))?;
+
+    // The connection is shared with other terminals, so only this channel is closed.
+    // Closing it hangs up just this multiplexer client; the session, and any other
+    // client attached to it, keeps running
+    let executions = terminal.current_executions.lock().await;
+    if let Some(bash_exec) = executions.first() {
+        if terminal.session.is_none() {
+            // Before closing the channel, try to close the shell gracefully
             // Send exit command
             let _ = bash_exec.stdin_tx.send(b"exit\n".to_vec()).await;
         }
+        let _ = bash_exec.control_tx.send(TerminalControl::Close).await;
     }
 
-    terminals.retain(|t| t.id != terminal_id);
+    Ok(())
+}
+
+/// Starts the shell of a terminal again after it exited, on a fresh channel from
+/// the project's connection pool. Scrollback and command history are kept.
+#[tauri::command]
+pub async fn restart_terminal(app: AppHandle, key: String, terminal_id: String) -> Result<(), String> {
+    let project = get_project_by_key(&key)?;
+
+    // Only a copy of the terminal's handles is kept, so other terminals stay usable
+    // while the channel is opened, possibly on a new connection
+    let terminal = {
+        let terminals = project
+            .terminal_connections
+            .lock()
+            .await;
 
-    if terminals.len() == initial_len {
-        return Err("Terminal not found".to_string());
+        terminals
+            .iter()
+            .find(|t| t.id == terminal_id)
+            .cloned()
+            .ok_or_else(|| "Terminal not found".to_string())?
+    };
+
+    if !terminal.current_executions.lock().await.is_empty() {
+        return Err("Terminal is still running".to_string());
     }
 
-    Ok(())
+    *terminal.exit.lock().await = None;
+    terminal.commands.lock().await.shell_restarted();
+
+    // The old shell's screen, alternate buffer and modes must not leak into the new one
+    let pty = terminal.launch.lock().await.pty.clone();
+    *terminal.screen.lock().await = Screen::new(pty.cols, pty.rows);
+
+    start_shell(app, Arc::clone(&project), &terminal).await
 }
 
+/// Lists terminals with their start path and the shell's current directory,
+/// which falls back to the start path until the shell reports one.
 #[tauri::command]
-pub async fn list_terminals(key: String) -> Result<Vec<(String, String)>, String> {
+pub async fn list_terminals(key: String) -> Result<Vec<TerminalInfo>, String> {
     let project = get_project_by_key(&key)?;
 
     let terminals = project
@@ -249,19 +594,110 @@ pub async fn list_terminals(key: String) -> Result<Vec<(String, String)>, String
         .lock()
         .await;
 
-    let result: Vec<(String, String)> = terminals
-        .iter()
-        .map(|t| (t.id.clone(), t.path.clone()))
-        .collect();
+    let mut result = Vec::with_capacity(terminals.len());
+    for t in terminals.iter() {
+        let cwd = t.commands.lock().await.cwd.clone();
+        let exit = t.exit.lock().await.clone();
+        let recording = t
+            .recorder
+            .lock()
+            .await
+            .as_ref()
+            .map(|r| r.path().to_string_lossy().to_string());
+        let log_file = t
+            .logger
+            .lock()
+            .await
+            .as_ref()
+            .map(|l| l.path().to_string_lossy().to_string());
+        result.push(TerminalInfo {
+            id: t.id.clone(),
+            path: t.path.clone(),
+            cwd: cwd.unwrap_or_else(|| t.path.clone()),
+            session: t.session.as_ref().map(|s| s.name.clone()),
+            multiplexer: t.session.as_ref().map(|s| s.multiplexer.name().to_string()),
+            exit,
+            recording,
+            log_file,
+        });
+    }
 
     Ok(result)
 }
 
+/// Returns buffered output since `since`, or everything still buffered when omitted.
+#[tauri::command]
+pub async fn get_terminal_content(
+    key: String,
+    terminal_id: String,
+    since: Option<u64>,
+) -> Result<TerminalContent, String> {
+    let project = get_project_by_key(&key)?;
+
+    let scrollback = {
+        let terminals = project
+            .terminal_connections
+            .lock()
+            .await;
+
+        let terminal = terminals
+            .iter()
+            .find(|t| t.id == terminal_id)
+            .ok_or_else(|| "Terminal not found".to_string())?;
+
+        Arc::clone(&terminal.scrollback)
+    };
+
+    let scrollback = scrollback.lock().await;
+    let chunk = scrollback.read_since(since.unwrap_or_else(|| scrollback.start_offset()));
+
+    Ok(TerminalContent {
+        data: chunk.data,
+        offset: chunk.offset,
+        next_offset: chunk.next_offset,
+        truncated: chunk.truncated,
+    })
+}
+
+/// Returns what the terminal currently shows, so a window that opens or reattaches
+/// can draw full-screen programs without replaying the scrollback.
+#[tauri::command]
+pub async fn get_terminal_screen(key: String, terminal_id: String) -> Result<ScreenSnapshot, String> {
+    let project = get_project_by_key(&key)?;
+
+    let screen = {
+        let terminals = project
+            .terminal_connections
+            .lock()
+            .await;
+
+        let terminal = terminals
+            .iter()
+            .find(|t| t.id == terminal_id)
+            .ok_or_else(|| "Terminal not found".to_string())?;
+
+        Arc::clone(&terminal.screen)
+    };
+
+    let snapshot = screen.lock().await.snapshot();
+    Ok(snapshot)
+}
+
+/// Searches the buffered output for `query`, literally or as a regex, ignoring escape
+/// sequences. Match offsets can be used to highlight hits in the output.
 #[tauri::command]
-pub async fn get_terminal_content(key: String, terminal_id: String) -> Result<String, String> {
+pub async fn search_terminal_scrollback(
+    key: String,
+    terminal_id: String,
+    query: String,
+    regex: Option<bool>,
+    case_sensitive: Option<bool>,
+    max_results: Option<usize>,
+) -> Result<TerminalSearchResult, String> {
+    let pattern = build_pattern(&query, regex.unwrap_or(false), case_sensitive.unwrap_or(false))?;
     let project = get_project_by_key(&key)?;
 
-    let content_lines = {
+    let scrollback = {
         let terminals = project
             .terminal_connections
             .lock()
@@ -272,18 +708,31 @@ pub async fn get_terminal_content(key: String, terminal_id: String) -> Result<St
             .find(|t| t.id == terminal_id)
             .ok_or_else(|| "Terminal not found".to_string())?;
 
-        Arc::clone(&terminal.content_lines)
+        Arc::clone(&terminal.scrollback)
     };
 
-    let content = content_lines.lock().await;
-    Ok(content.clone())
+    // Raw bytes keep match offsets exact even where the output isn't valid UTF-8
+    let (start_offset, raw) = scrollback.lock().await.raw();
+
+    let (matches, limited) = search_output(
+        &raw,
+        start_offset,
+        &pattern,
+        max_results.unwrap_or(DEFAULT_SEARCH_RESULTS),
+    );
+
+    Ok(TerminalSearchResult {
+        matches,
+        limited,
+        start_offset,
+    })
 }
 
 #[tauri::command]
 pub async fn clear_terminal_content(key: String, terminal_id: String) -> Result<(), String> {
     let project = get_project_by_key(&key)?;
 
-    let content_lines = {
+    let scrollback = {
         let terminals = project
             .terminal_connections
             .lock()
@@ -294,10 +743,459 @@ pub async fn clear_terminal_content(key: String, terminal_id: String) -> Result<
             .find(|t| t.id == terminal_id)
             .ok_or_else(|| "Terminal not found".to_string())?;
 
-        Arc::clone(&terminal.content_lines)
+        Arc::clone(&terminal.scrollback)
     };
 
-    let mut content = content_lines.lock().await;
-    content.clear();
+    scrollback.lock().await.clear();
     Ok(())
-}
\ No newline at end of file
+}
+
+#[tauri::command]
+pub async fn resize_terminal(
+    key: String,
+    terminal_id: String,
+    cols: u32,
+    rows: u32,
+) -> Result<(), String> {
+    if cols == 0 || rows == 0 {
+        return Err("Terminal size must be at least 1x1".to_string());
+    }
+    let cols = cols.min(MAX_COLS);
+    let rows = rows.min(MAX_ROWS);
+
+    let project = get_project_by_key(&key)?;
+
+    let (current_executions, launch, recorder, screen) = {
+        let terminals = project
+            .terminal_connections
+            .lock()
+            .await;
+
+        let terminal = terminals
+            .iter()
+            .find(|t| t.id == terminal_id)
+            .ok_or_else(|| "Terminal not found".to_string())?;
+
+        (
+            Arc::clone(&terminal.current_executions),
+            Arc::clone(&terminal.launch),
+            Arc::clone(&terminal.recorder),
+            Arc::clone(&terminal.screen),
+        )
+    };
+
+    // A restarted shell should come back with the latest size
+    {
+        let mut launch = launch.lock().await;
+        launch.pty.cols = cols;
+        launch.pty.rows = rows;
+    }
+    screen.lock().await.resize(cols, rows);
+
+    let executions = current_executions.lock().await;
+
+    if let Some(bash_exec) = executions.first() {
+        bash_exec
+            .control_tx
+            .send(TerminalControl::Resize { cols, rows })
+            .await
+            .map_err(|e| format!("Failed to resize terminal: {}", e))?;
+
+        if let Some(recorder) = recorder.lock().await.as_mut() {
+            let _ = recorder.resize(cols, rows);
+        }
+
+        Ok(())
+    } else {
+        Err("No active shell session".to_string())
+    }
+}
+
+/// Lists the commands recorded through shell integration, oldest first,
+/// including the one still running.
+#[tauri::command]
+pub async fn list_terminal_commands(
+    key: String,
+    terminal_id: String,
+) -> Result<Vec<CommandRecord>, String> {
+    let project = get_project_by_key(&key)?;
+
+    let commands = {
+        let terminals = project
+            .terminal_connections
+            .lock()
+            .await;
+
+        let terminal = terminals
+            .iter()
+            .find(|t| t.id == terminal_id)
+            .ok_or_else(|| "Terminal not found".to_string())?;
+
+        Arc::clone(&terminal.commands)
+    };
+
+    let tracker = commands.lock().await;
+    let mut records: Vec<CommandRecord> = tracker.records.iter().cloned().collect();
+    records.extend(tracker.current.clone());
+
+    Ok(records)
+}
+
+/// Moves the shell to `path`, e.g. to follow the explorer. Refused while a command runs.
+#[tauri::command]
+pub async fn change_terminal_directory(
+    key: String,
+    terminal_id: String,
+    path: String,
+) -> Result<(), String> {
+    let project = get_project_by_key(&key)?;
+
+    let (current_executions, commands) = {
+        let terminals = project
+            .terminal_connections
+            .lock()
+            .await;
+
+        let terminal = terminals
+            .iter()
+            .find(|t| t.id == terminal_id)
+            .ok_or_else(|| "Terminal not found".to_string())?;
+
+        (Arc::clone(&terminal.current_executions), Arc::clone(&terminal.commands))
+    };
+
+    {
+        let tracker = commands.lock().await;
+        // Without shell integration a full-screen program or a half-typed line
+        // could be on the prompt, and the cd would be typed into it
+        if !tracker.integrated {
+            return Err("Shell state is unknown; cannot change directory safely".to_string());
+        }
+        if tracker.current.is_some() {
+            return Err("Terminal is busy running a command".to_string());
+        }
+    }
+
+    let executions = current_executions.lock().await;
+
+    if let Some(bash_exec) = executions.first() {
+        // Leading space keeps the cd out of shell history where HISTCONTROL allows it
+        let command = format!(" cd {}\n", shell_quote(&path));
+        bash_exec
+            .stdin_tx
+            .send(command.into_bytes())
+            .await
+            .map_err(|e| format!("Failed to send command to shell: {}", e))?;
+
+        Ok(())
+    } else {
+        Err("No active shell session".to_string())
+    }
+}
+
+/// Lists tmux and screen sessions on the server that terminals can reattach to.
+#[tauri::command]
+pub async fn list_remote_sessions(key: String) -> Result<Vec<RemoteSession>, String> {
+    let project = get_project_by_key(&key)?;
+    list_sessions(&project.main_connection).await
+}
+
+#[tauri::command]
+pub async fn kill_remote_session(
+    key: String,
+    multiplexer: String,
+    session_name: String,
+) -> Result<(), String> {
+    let project = get_project_by_key(&key)?;
+    validate_session_name(&session_name)?;
+
+    Multiplexer::parse(&multiplexer)?
+        .kill(&project.main_connection, &session_name)
+        .await
+}
+
+/// Lists the shells available on the server for terminal creation.
+#[tauri::command]
+pub async fn list_available_shells(key: String) -> Result<Vec<ShellInfo>, String> {
+    let project = get_project_by_key(&key)?;
+    detect_shells(&project.main_connection).await
+}
+
+/// Sends a signal (INT, TERM, KILL, HUP, QUIT) or "EOF" to the terminal's shell.
+/// INT and QUIT are typed as their control characters so they reach the foreground job.
+#[tauri::command]
+pub async fn send_terminal_signal(
+    key: String,
+    terminal_id: String,
+    signal: String,
+) -> Result<(), String> {
+    let control = if signal.eq_ignore_ascii_case("EOF") {
+        TerminalControl::Eof
+    } else {
+        TerminalControl::Signal(parse_signal(&signal)?)
+    };
+
+    let project = get_project_by_key(&key)?;
+
+    let current_executions = {
+        let terminals = project
+            .terminal_connections
+            .lock()
+            .await;
+
+        let terminal = terminals
+            .iter()
+            .find(|t| t.id == terminal_id)
+            .ok_or_else(|| "Terminal not found".to_string())?;
+
+        Arc::clone(&terminal.current_executions)
+    };
+
+    let executions = current_executions.lock().await;
+
+    if let Some(bash_exec) = executions.first() {
+        bash_exec
+            .control_tx
+            .send(control)
+            .await
+            .map_err(|e| format!("Failed to send signal: {}", e))?;
+
+        Ok(())
+    } else {
+        Err("No active shell session".to_string())
+    }
+}
+
+/// Starts recording a terminal to an asciicast v2 file in the app's recordings directory.
+/// `name` picks the file name; directories in it are ignored. Input is only recorded
+/// when `record_input` is set. Returns the file path.
+#[tauri::command]
+pub async fn start_terminal_recording(
+    app: AppHandle,
+    key: String,
+    terminal_id: String,
+    name: Option<String>,
+    record_input: Option<bool>,
+) -> Result<String, String> {
+    let project = get_project_by_key(&key)?;
+
+    let (recorder, launch) = {
+        let terminals = project
+            .terminal_connections
+            .lock()
+            .await;
+
+        let terminal = terminals
+            .iter()
+            .find(|t| t.id == terminal_id)
+            .ok_or_else(|| "Terminal not found".to_string())?;
+
+        (Arc::clone(&terminal.recorder), Arc::clone(&terminal.launch))
+    };
+
+    let mut active = recorder.lock().await;
+    if active.is_some() {
+        return Err("Terminal is already being recorded".to_string());
+    }
+
+    let file_name = match name.filter(|n| !n.trim().is_empty()) {
+        Some(name) => {
+            let mut file_name = recording_file_name(&name)?;
+            if !file_name.ends_with(".cast") {
+                file_name.push_str(".cast");
+            }
+            file_name
+        }
+        None => format!(
+            "{}-{}-{}.cast",
+            project.key,
+            &terminal_id[..8.min(terminal_id.len())],
+            now_millis() / 1000
+        ),
+    };
+    let path = recordings_dir(&app)?.join(file_name);
+
+    let pty = launch.lock().await.pty.clone();
+    let title = format!("{}@{} - {}", project.user, project.host, project.name);
+    *active = Some(Recorder::create(
+        &path,
+        pty.cols,
+        pty.rows,
+        &pty.term,
+        &title,
+        record_input.unwrap_or(false),
+    )?);
+    drop(active);
+
+    let recorded_path = path.clone();
+    spawn_flush_task(recorder, move |recorder: &mut Recorder| {
+        recorder.path() == recorded_path && recorder.flush().is_ok()
+    });
+
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Stops recording a terminal and returns the path of the finished file.
+#[tauri::command]
+pub async fn stop_terminal_recording(key: String, terminal_id: String) -> Result<String, String> {
+    let project = get_project_by_key(&key)?;
+
+    let recorder = {
+        let terminals = project
+            .terminal_connections
+            .lock()
+            .await;
+
+        let terminal = terminals
+            .iter()
+            .find(|t| t.id == terminal_id)
+            .ok_or_else(|| "Terminal not found".to_string())?;
+
+        Arc::clone(&terminal.recorder)
+    };
+
+    let mut recorder = recorder
+        .lock()
+        .await
+        .take()
+        .ok_or_else(|| "Terminal is not being recorded".to_string())?;
+    recorder.flush()?;
+
+    Ok(recorder.path().to_string_lossy().to_string())
+}
+
+/// Loads a recorded `.cast` file from the recordings directory as frames for replay.
+#[tauri::command]
+pub async fn get_terminal_recording(app: AppHandle, name: String) -> Result<Recording, String> {
+    let path = recordings_dir(&app)?.join(recording_file_name(&name)?);
+    read_recording(&path)
+}
+
+fn recordings_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?
+        .join("recordings"))
+}
+
+// Keeps only the last component, so names cannot point outside the recordings directory
+fn recording_file_name(name: &str) -> Result<String, String> {
+    Path::new(name.trim())
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| format!("Invalid recording name: {}", name))
+}
+
+// Flushes the buffered writer in `slot` every FLUSH_INTERVAL until `flush` returns
+// false, which it does once the writer was stopped or replaced
+fn spawn_flush_task<T, F>(slot: Arc<Mutex<Option<T>>>, mut flush: F)
+where
+    T: Send + 'static,
+    F: FnMut(&mut T) -> bool + Send + 'static,
+{
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(FLUSH_INTERVAL).await;
+            match slot.lock().await.as_mut() {
+                Some(writer) if flush(writer) => {}
+                _ => break,
+            }
+        }
+    });
+}
+
+/// Starts appending a terminal's output to a local log file, with escape sequences
+/// stripped unless `raw` is set, rotating it by size. Files go to the app data directory unless `directory`
+/// is given. Returns the log file path.
+#[tauri::command]
+pub async fn start_terminal_logging(
+    app: AppHandle,
+    key: String,
+    terminal_id: String,
+    directory: Option<String>,
+    raw: Option<bool>,
+    max_bytes: Option<u64>,
+    max_files: Option<usize>,
+) -> Result<String, String> {
+    let project = get_project_by_key(&key)?;
+
+    let logger = {
+        let terminals = project
+            .terminal_connections
+            .lock()
+            .await;
+
+        let terminal = terminals
+            .iter()
+            .find(|t| t.id == terminal_id)
+            .ok_or_else(|| "Terminal not found".to_string())?;
+
+        Arc::clone(&terminal.logger)
+    };
+
+    let mut active = logger.lock().await;
+    if active.is_some() {
+        return Err("Terminal is already being logged".to_string());
+    }
+
+    let directory = match directory.filter(|d| !d.trim().is_empty()) {
+        Some(directory) => PathBuf::from(directory),
+        None => app
+            .path()
+            .app_data_dir()
+            .map_err(|e| format!("Failed to resolve app data directory: {}", e))?
+            .join("logs"),
+    };
+    let file_name = format!(
+        "{}-{}-{}.log",
+        project.key,
+        &terminal_id[..8.min(terminal_id.len())],
+        now_millis() / 1000
+    );
+    let path = directory.join(file_name);
+
+    *active = Some(TerminalLogger::create(
+        &path,
+        !raw.unwrap_or(false),
+        max_bytes.unwrap_or(DEFAULT_LOG_MAX_BYTES),
+        max_files.unwrap_or(DEFAULT_LOG_MAX_FILES),
+    )?);
+    drop(active);
+
+    let logged_path = path.clone();
+    spawn_flush_task(logger, move |logger: &mut TerminalLogger| {
+        logger.path() == logged_path && logger.flush().is_ok()
+    });
+
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Stops logging a terminal and returns the path of its log file.
+#[tauri::command]
+pub async fn stop_terminal_logging(key: String, terminal_id: String) -> Result<String, String> {
+    let project = get_project_by_key(&key)?;
+
+    let logger = {
+        let terminals = project
+            .terminal_connections
+            .lock()
+            .await;
+
+        let terminal = terminals
+            .iter()
+            .find(|t| t.id == terminal_id)
+            .ok_or_else(|| "Terminal not found".to_string())?;
+
+        Arc::clone(&terminal.logger)
+    };
+
+    let mut logger = logger
+        .lock()
+        .await
+        .take()
+        .ok_or_else(|| "Terminal is not being logged".to_string())?;
+    logger.flush()?;
+
+    Ok(logger.path().to_string_lossy().to_string())
+}
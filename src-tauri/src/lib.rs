@@ -1,8 +1,11 @@
 mod auth;
 mod commands;
 mod models;
+mod paths;
+mod persistence;
 mod ssh;
 mod state;
+mod terminal;
 
 use tauri_plugin_store::StoreExt;
 
@@ -43,7 +46,11 @@ pub fn run() {
             commands::connection::close_project,
             commands::connection::get_project,
             commands::connection::get_current_pwd,
+            commands::connection::get_start_directory,
             commands::connection::get_desktop_environment,
+            commands::exec::run_command,
+            commands::fleet::run_fleet_command,
+            commands::fleet::export_fleet_results,
             commands::terminal::create_terminal,
             commands::terminal::execute_terminal_command,
             commands::terminal::send_terminal_input,
@@ -51,10 +58,54 @@ pub fn run() {
             commands::terminal::list_terminals,
             commands::terminal::clear_terminal_content,
             commands::terminal::get_terminal_content,
+            commands::terminal::get_terminal_screen,
+            commands::terminal::search_terminal_scrollback,
+            commands::terminal::resize_terminal,
+            commands::terminal::list_terminal_commands,
+            commands::terminal::change_terminal_directory,
+            commands::terminal::list_remote_sessions,
+            commands::terminal::kill_remote_session,
+            commands::terminal::list_available_shells,
+            commands::terminal::send_terminal_signal,
+            commands::terminal::restart_terminal,
+            commands::terminal::start_terminal_recording,
+            commands::terminal::stop_terminal_recording,
+            commands::terminal::get_terminal_recording,
+            commands::terminal::start_terminal_logging,
+            commands::terminal::stop_terminal_logging,
+            commands::history::search_command_history,
+            commands::history::remove_history_entry,
+            commands::history::clear_command_history,
+            commands::snippets::list_snippets,
+            commands::snippets::save_snippet,
+            commands::snippets::delete_snippet,
+            commands::snippets::run_snippet,
+            commands::broadcast::join_broadcast_group,
+            commands::broadcast::leave_broadcast_group,
+            commands::broadcast::list_broadcast_groups,
+            commands::broadcast::broadcast_input,
+            commands::broadcast::broadcast_command,
             commands::filesystem::get_dir_contents,
             commands::filesystem::create_file,
             commands::filesystem::create_folder,
-            commands::filesystem::delete_item
+            commands::filesystem::delete_item,
+            commands::filesystem::complete_path,
+            commands::filesystem::resolve_path,
+            commands::bookmarks::list_bookmarks,
+            commands::bookmarks::add_bookmark,
+            commands::bookmarks::remove_bookmark,
+            commands::bookmarks::move_bookmark,
+            commands::bookmarks::list_recent_directories,
+            commands::bookmarks::remove_recent_directory,
+            commands::bookmarks::clear_recent_directories,
+            commands::bookmarks::get_start_directory_settings,
+            commands::bookmarks::set_start_directory_settings,
+            commands::local_edit::open_remote_file,
+            commands::local_edit::force_upload_opened_file,
+            commands::local_edit::close_opened_file,
+            commands::local_edit::list_opened_files,
+            commands::settings::get_project_settings,
+            commands::settings::set_project_settings
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -1,24 +1,138 @@
+use crate::ssh::ConnectionPool;
+use crate::terminal::buffer::ScrollbackBuffer;
+use crate::terminal::multiplexer::PersistentSession;
+use crate::terminal::logging::TerminalLogger;
+use crate::terminal::recording::Recorder;
+use crate::terminal::screen::Screen;
+use crate::terminal::search::ScrollbackMatch;
+use crate::terminal::session::PtyOptions;
+use crate::terminal::shell_integration::{CommandRecord, CommandTracker};
+use crate::terminal::sudo::SudoResponder;
 use async_ssh2_tokio::client::Client;
 use serde::{Deserialize, Serialize};
 use tokio::sync::{Mutex, mpsc};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Instant;
+use tauri::async_runtime::JoinHandle;
 
 #[allow(dead_code)]
 #[derive(Clone)]
 pub struct TerminalConnection {
     pub id: String,
-    pub connection: Arc<Client>,
-    pub content_lines: Arc<Mutex<String>>, // Changed from Vec<String> to String
+    pub scrollback: Arc<Mutex<ScrollbackBuffer>>,
     pub current_executions: Arc<Mutex<Vec<TerminalExecution>>>,
     pub path: String,
+    // Only present when automatic sudo password entry is enabled for the project
+    pub sudo: Option<Arc<Mutex<SudoResponder>>>,
+    pub commands: Arc<Mutex<CommandTracker>>,
+    // Set when the shell runs inside a tmux or screen session that outlives the connection
+    pub session: Option<PersistentSession>,
+    pub exit: Arc<Mutex<Option<TerminalExit>>>,
+    pub launch: Arc<Mutex<TerminalLaunch>>,
+    // Set while the terminal is being recorded to an asciicast file
+    pub recorder: Arc<Mutex<Option<Recorder>>>,
+    // Set while the terminal's output is appended to a local log file
+    pub logger: Arc<Mutex<Option<TerminalLogger>>>,
+    // What the terminal currently shows, for windows that open or reattach later
+    pub screen: Arc<Mutex<Screen>>,
+}
+
+/// Everything needed to start (or restart) the shell of a terminal.
+#[derive(Clone)]
+pub struct TerminalLaunch {
+    pub shell_command: String,
+    pub pty: PtyOptions,
+    pub init_command: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct TerminalOutputEvent {
+    pub key: String,
+    pub terminal_id: String,
+    pub data: String,
+    pub offset: u64,
+    pub next_offset: u64,
+}
+
+#[derive(Clone, Serialize)]
+pub struct TerminalCommandEvent {
+    pub key: String,
+    pub terminal_id: String,
+    pub record: CommandRecord,
+}
+
+#[derive(Clone, Serialize)]
+pub struct TerminalCwdEvent {
+    pub key: String,
+    pub terminal_id: String,
+    pub cwd: String,
+}
+
+#[derive(Serialize)]
+pub struct TerminalInfo {
+    pub id: String,
+    pub path: String,
+    pub cwd: String,
+    pub session: Option<String>,
+    pub multiplexer: Option<String>,
+    pub exit: Option<TerminalExit>,
+    /// Local file the terminal is being recorded to
+    pub recording: Option<String>,
+    /// Local file the terminal's output is logged to
+    pub log_file: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct TerminalSearchResult {
+    pub matches: Vec<ScrollbackMatch>,
+    /// More matches exist than were returned
+    pub limited: bool,
+    /// Oldest offset that was searched; older output is no longer buffered
+    pub start_offset: u64,
+}
+
+#[derive(Serialize)]
+pub struct TerminalContent {
+    pub data: String,
+    pub offset: u64,
+    pub next_offset: u64,
+    pub truncated: bool,
 }
 
 #[allow(dead_code)]
 pub struct TerminalExecution {
     pub stdin_tx: mpsc::Sender<Vec<u8>>,
+    pub control_tx: mpsc::Sender<TerminalControl>,
     pub command: String,
 }
 
+/// Out-of-band requests for a running PTY session.
+pub enum TerminalControl {
+    Resize { cols: u32, rows: u32 },
+    Signal(russh::Sig),
+    Eof,
+    /// Closes the channel without waiting for the shell to exit
+    Close,
+}
+
+/// How the shell of a terminal ended.
+#[derive(Clone, Default, Serialize)]
+pub struct TerminalExit {
+    pub exit_status: Option<u32>,
+    pub signal: Option<String>,
+    pub core_dumped: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct TerminalExitEvent {
+    pub key: String,
+    pub terminal_id: String,
+    pub exit: TerminalExit,
+}
+
 #[allow(dead_code)]
 #[derive(Clone)]
 pub struct Project {
@@ -31,7 +145,56 @@ pub struct Project {
     pub public_key_file: Option<String>,
     pub auth_method: String,
     pub main_connection: Arc<Client>,
+    /// Connections terminal channels are opened on, led by `main_connection`
+    pub terminal_pool: Arc<Mutex<ConnectionPool>>,
     pub terminal_connections: Arc<Mutex<Vec<TerminalConnection>>>,
+    pub path_cache: Arc<Mutex<PathCache>>,
+    pub opened_files: Arc<Mutex<Vec<OpenedFile>>>,
+}
+
+/// A remote file downloaded to a temp directory and opened in a local application.
+pub struct OpenedFile {
+    pub remote_path: String,
+    pub local_path: PathBuf,
+    pub temp_dir: PathBuf,
+    pub remote_stamp: Arc<Mutex<RemoteStamp>>,
+    /// Set while local edits are not on the server, after a conflict or failed upload
+    pub unsynced: Arc<Mutex<bool>>,
+    pub watcher: JoinHandle<()>,
+}
+
+/// Remote modification time and size, used to detect changes made on the server.
+#[derive(Clone, PartialEq)]
+pub struct RemoteStamp {
+    pub modified: i64,
+    pub size: u64,
+}
+
+#[derive(Clone, Serialize)]
+pub struct RemoteFileEvent {
+    pub key: String,
+    pub path: String,
+    pub error: Option<String>,
+}
+
+/// Recently fetched remote data used by path completion.
+#[derive(Default)]
+pub struct PathCache {
+    pub listings: HashMap<String, CachedListing>,
+    pub homes: HashMap<String, String>,
+}
+
+pub struct CachedListing {
+    pub fetched_at: Instant,
+    pub entries: Vec<CompletionEntry>,
+}
+
+#[derive(Clone)]
+pub struct CompletionEntry {
+    pub name: String,
+    pub is_dir: bool,
+    pub is_linked: bool,
+    pub is_executable: bool,
 }
 
 #[derive(Serialize)]
@@ -51,4 +214,188 @@ pub struct DirEntry {
     pub size: String,
     pub permissions: String,
     pub modified: String,
-}
\ No newline at end of file
+}
+
+#[derive(Serialize)]
+pub struct PathCompletion {
+    pub name: String,
+    pub path: String,
+    pub is_dir: bool,
+    pub is_linked: bool,
+    pub marker: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub name: String,
+    pub path: String,
+}
+
+/// Per-project directory data persisted in `store.json`.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectDirectories {
+    pub bookmarks: Vec<Bookmark>,
+    pub recent: Vec<String>,
+    pub start_directory: Option<String>,
+    pub remember_last_directory: bool,
+    pub last_directory: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct StartDirectorySettings {
+    pub start_directory: Option<String>,
+    pub remember_last_directory: bool,
+    pub last_directory: Option<String>,
+}
+
+/// Per-project preferences persisted in `store.json`.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectSettings {
+    pub auto_sudo: bool,
+    /// "tmux" or "screen" to keep new terminals alive on the server
+    pub multiplexer: Option<String>,
+    /// Shell name or path for new terminals; the user's login shell when unset
+    pub shell: Option<String>,
+    pub login_shell: bool,
+    pub environment: BTreeMap<String, String>,
+    /// Typed into every new terminal once the shell has started
+    pub init_command: Option<String>,
+    /// Channels one connection may carry; set to the server's MaxSessions when it is lowered
+    pub max_sessions: Option<usize>,
+    /// Keep commands that look like they contain credentials in the command history
+    pub record_secret_commands: bool,
+}
+
+/// A command sent through `execute_terminal_command`, kept once however often it was used.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub command: String,
+    pub count: u32,
+    /// Unix time in milliseconds
+    pub last_used: u64,
+}
+
+/// A saved command template. `{{path}}`, `{{selected_files}}`, `{{project}}`, `{{host}}`
+/// and `{{user}}` are filled in automatically; any other `{{name}}` is prompted for.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Snippet {
+    pub id: String,
+    pub name: String,
+    pub command: String,
+    pub description: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct SnippetInfo {
+    pub id: String,
+    pub name: String,
+    pub command: String,
+    pub description: Option<String>,
+    /// "global" or "project"
+    pub scope: String,
+    /// Placeholders the user has to fill in before running
+    pub placeholders: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, Serialize)]
+pub struct BroadcastMember {
+    pub key: String,
+    pub terminal_id: String,
+}
+
+#[derive(Serialize)]
+pub struct BroadcastGroup {
+    pub name: String,
+    pub members: Vec<BroadcastMember>,
+}
+
+/// A broadcast member that could not be sent to.
+#[derive(Serialize)]
+pub struct BroadcastFailure {
+    pub key: String,
+    pub terminal_id: String,
+    pub error: String,
+}
+
+#[derive(Serialize)]
+pub struct RunCommandResult {
+    pub run_id: String,
+    pub stdout: String,
+    pub stderr: String,
+    /// Missing when the command was killed by a signal or timed out
+    pub exit_status: Option<u32>,
+    pub signal: Option<String>,
+    pub duration_ms: u64,
+    pub timed_out: bool,
+}
+
+#[derive(Clone, Serialize)]
+pub struct CommandOutputEvent {
+    pub key: String,
+    pub run_id: String,
+    /// "stdout" or "stderr"
+    pub stream: String,
+    pub data: String,
+}
+
+/// A project as saved by the project list in `store.json`.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedProject {
+    pub name: String,
+    pub host: String,
+    pub user: String,
+    pub password: Option<String>,
+    pub key_file: Option<String>,
+    pub public_key_file: Option<String>,
+    pub auth_method: String,
+}
+
+/// Outcome of a fleet command on one project.
+#[derive(Default, Serialize, Deserialize)]
+pub struct FleetHostResult {
+    pub key: String,
+    pub name: String,
+    pub host: String,
+    pub exit_status: Option<u32>,
+    pub signal: Option<String>,
+    pub stdout: String,
+    pub stderr: String,
+    pub duration_ms: u64,
+    pub timed_out: bool,
+    /// Set when the host could not be reached or the command could not be started
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct CommandOutput {
+    pub stdout: String,
+    pub stderr: String,
+    pub exit_status: u32,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CommandHistory {
+    /// Most recently used first
+    pub entries: Vec<HistoryEntry>,
+}
+
+/// Optional settings for a new terminal. Shell fields override the project settings.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct TerminalOptions {
+    pub cols: Option<u32>,
+    pub rows: Option<u32>,
+    pub term: Option<String>,
+    pub scrollback_bytes: Option<usize>,
+    pub multiplexer: Option<String>,
+    pub session_name: Option<String>,
+    pub shell: Option<String>,
+    pub login: Option<bool>,
+    /// Added to the project environment, replacing variables with the same name
+    pub environment: BTreeMap<String, String>,
+    pub init_command: Option<String>,
+}
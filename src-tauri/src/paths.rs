@@ -0,0 +1,120 @@
+use crate::models::PathCache;
+use async_ssh2_tokio::client::Client;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Lexically normalizes a remote path, collapsing duplicate slashes, `.` and `..`.
+/// `..` never climbs above `/`; relative paths keep their leading `..` segments.
+pub fn normalize_path(path: &str) -> String {
+    let is_absolute = path.starts_with('/');
+    let mut segments: Vec<&str> = Vec::new();
+
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => match segments.last() {
+                Some(&last) if last != ".." => {
+                    segments.pop();
+                }
+                _ if is_absolute => {}
+                _ => segments.push(".."),
+            },
+            _ => segments.push(segment),
+        }
+    }
+
+    let joined = segments.join("/");
+    if is_absolute {
+        format!("/{}", joined)
+    } else if joined.is_empty() {
+        ".".to_string()
+    } else {
+        joined
+    }
+}
+
+/// Joins `path` onto `base` unless it is already absolute, then normalizes the result.
+pub fn join_path(base: &str, path: &str) -> String {
+    if path.starts_with('/') {
+        normalize_path(path)
+    } else {
+        normalize_path(&format!("{}/{}", base, path))
+    }
+}
+
+/// Wraps a value in single quotes so the remote shell treats it as one literal word.
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Runs a POSIX `script` through `sh`, since the login shell may be fish, csh or similar.
+pub fn posix_script(script: &str) -> String {
+    format!("sh -c {}", shell_quote(script))
+}
+
+/// Expands a leading `~` or `~user` using the remote shell, caching home directories per project.
+pub async fn expand_home(
+    connection: &Arc<Client>,
+    cache: &Arc<Mutex<PathCache>>,
+    path: &str,
+) -> Result<String, String> {
+    if !path.starts_with('~') {
+        return Ok(path.to_string());
+    }
+
+    let (user_part, rest) = match path.find('/') {
+        Some(pos) => (&path[..pos], &path[pos..]),
+        None => (path, ""),
+    };
+    let user = &user_part[1..];
+
+    if let Some(home) = cache.lock().await.homes.get(user) {
+        return Ok(format!("{}{}", home, rest));
+    }
+
+    let command = if user.is_empty() {
+        "printf '%s' \"$HOME\"".to_string()
+    } else {
+        if !user
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.')
+        {
+            return Err(format!("Invalid user name: {}", user));
+        }
+        format!("printf '%s' ~{}", user)
+    };
+
+    let result = connection
+        .execute(&command)
+        .await
+        .map_err(|e| format!("Failed to expand home directory: {}", e))?;
+
+    let home = result.stdout.trim_end_matches('\n').to_string();
+    if result.exit_status != 0 || !home.starts_with('/') {
+        return Err(format!("Unknown user: {}", user));
+    }
+
+    cache.lock().await.homes.insert(user.to_string(), home.clone());
+    Ok(format!("{}{}", home, rest))
+}
+
+/// Resolves symlinks on the server and returns the canonical path.
+/// Missing trailing components are allowed so not-yet-created paths still resolve.
+pub async fn real_path(connection: &Arc<Client>, path: &str) -> Result<String, String> {
+    let quoted = shell_quote(path);
+    let command = format!(
+        "realpath -m -- {0} 2>/dev/null || readlink -f -- {0}",
+        quoted
+    );
+    let result = connection
+        .execute(&command)
+        .await
+        .map_err(|e| format!("Failed to resolve path: {}", e))?;
+
+    let resolved = result.stdout.trim_end_matches('\n');
+    if result.exit_status != 0 || resolved.is_empty() {
+        return Err(format!("Failed to resolve path: {}", result.stderr.trim()));
+    }
+
+    Ok(resolved.to_string())
+}
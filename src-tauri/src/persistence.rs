@@ -0,0 +1,99 @@
+use lazy_static::lazy_static;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+const STORE_FILE: &str = "store.json";
+
+lazy_static! {
+    // Updates load, modify and save; running them one at a time keeps two of them,
+    // foreground or background, from overwriting each other's change
+    static ref UPDATE_LOCK: Mutex<()> = Mutex::new(());
+}
+
+// The frontend keys projects by their lowercased name, so a root with uppercase
+// letters can never collide with a project entry
+const BACKEND_ROOT: &str = "__REMOTE_EXPLORER__";
+
+/// Store key for backend data that belongs to no project.
+pub fn backend_key(name: &str) -> String {
+    format!("{}/{}", BACKEND_ROOT, name)
+}
+
+/// Store key holding backend data for a project, kept apart from the project entry
+/// that the frontend writes under the bare project key.
+pub fn project_entry_key(project_key: &str, section: &str) -> String {
+    // The section is last and never contains '/', so project keys with '/' stay unambiguous
+    backend_key(&format!("projects/{}/{}", project_key, section))
+}
+
+pub fn load<T: DeserializeOwned + Default>(app: &AppHandle, key: &str) -> Result<T, String> {
+    let store = app
+        .store(STORE_FILE)
+        .map_err(|e| format!("Failed to open store: {}", e))?;
+
+    match store.get(key) {
+        Some(value) => serde_json::from_value(value)
+            .map_err(|e| format!("Failed to read {} from store: {}", key, e)),
+        None => Ok(T::default()),
+    }
+}
+
+pub fn contains(app: &AppHandle, key: &str) -> Result<bool, String> {
+    let store = app
+        .store(STORE_FILE)
+        .map_err(|e| format!("Failed to open store: {}", e))?;
+
+    Ok(store.has(key))
+}
+
+pub fn save<T: Serialize>(app: &AppHandle, key: &str, value: &T) -> Result<(), String> {
+    let store = app
+        .store(STORE_FILE)
+        .map_err(|e| format!("Failed to open store: {}", e))?;
+
+    let value = serde_json::to_value(value)
+        .map_err(|e| format!("Failed to serialize {}: {}", key, e))?;
+    store.set(key, value);
+    store
+        .save()
+        .map_err(|e| format!("Failed to save store: {}", e))
+}
+
+/// Loads the value under `key`, applies `update` and saves the result unless it is
+/// unchanged. Every read-modify-write of a backend key goes through here, so
+/// concurrent updates of the same key never drop each other's change.
+pub fn update<T, R, F>(app: &AppHandle, key: &str, update: F) -> Result<R, String>
+where
+    T: Serialize + DeserializeOwned + Default,
+    F: FnOnce(&mut T) -> Result<R, String>,
+{
+    let _guard = UPDATE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+    let mut value: T = load(app, key)?;
+    let before = serde_json::to_value(&value).ok();
+    let result = update(&mut value)?;
+
+    if serde_json::to_value(&value).ok() != before {
+        save(app, key, &value)?;
+    }
+    Ok(result)
+}
+
+/// Runs [`update`] on a blocking thread, so frequent bookkeeping never waits for the
+/// store file. Failures are dropped since callers treat this as best effort.
+pub fn update_in_background<T, F>(app: &AppHandle, key: String, apply: F)
+where
+    T: Serialize + DeserializeOwned + Default + 'static,
+    F: FnOnce(&mut T) + Send + 'static,
+{
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let _ = update(&app, &key, |value: &mut T| {
+            apply(value);
+            Ok(())
+        });
+    });
+}
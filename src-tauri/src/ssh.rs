@@ -1,5 +1,11 @@
+use crate::auth::build_auth_method;
+use crate::models::Project;
 use async_ssh2_tokio::client::{Client, ServerCheckMethod};
-use std::time::Duration;
+use russh::client::Msg;
+use russh::Channel;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
 use tokio::time::timeout;
 
 pub struct HostPort {
@@ -41,3 +47,146 @@ pub async fn connect_to_ssh(
         .map_err(|e| format!("SSH connection failed - {}", e))?
         .map_err(|e| format!("SSH connection failed - {}", e))
 }
+
+/// OpenSSH's default MaxSessions, the number of channels one connection may carry.
+pub const DEFAULT_MAX_SESSIONS: usize = 10;
+// Channels kept free on the main connection for explorer commands
+const RESERVED_MAIN_CHANNELS: usize = 2;
+// How long a connection that refused a channel is skipped before it is tried again
+const CHANNEL_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+struct PoolEntry {
+    client: Arc<Client>,
+    channels: usize,
+    limit: usize,
+    // Set after the connection refused a channel; cleared once one of its channels closes
+    retry_after: Option<Instant>,
+    // Only connections opened by the pool are disconnected by it
+    owned: bool,
+}
+
+/// Connections of a project that terminal channels are opened on, starting
+/// with the main connection and adding more once it is full.
+pub struct ConnectionPool {
+    entries: Vec<PoolEntry>,
+    max_sessions: usize,
+}
+
+impl ConnectionPool {
+    pub fn new(main_connection: Arc<Client>, max_sessions: usize) -> Self {
+        let max_sessions = max_sessions.max(1);
+        Self {
+            entries: vec![PoolEntry {
+                client: main_connection,
+                channels: 0,
+                limit: max_sessions.saturating_sub(RESERVED_MAIN_CHANNELS).max(1),
+                retry_after: None,
+                owned: false,
+            }],
+            max_sessions,
+        }
+    }
+
+    /// Opens a session channel on the first connection with room for it,
+    /// connecting again only when every pooled connection is full. The pool is only
+    /// locked to pick a connection and record the result, never across network I/O.
+    pub async fn open_channel(
+        pool: &Mutex<ConnectionPool>,
+        project: &Project,
+    ) -> Result<(Arc<Client>, Channel<Msg>), String> {
+        loop {
+            // A statement of its own, so the lock is released before the request
+            let reserved = pool.lock().await.reserve();
+            let Some(client) = reserved else {
+                break;
+            };
+
+            match client.get_channel().await {
+                Ok(channel) => return Ok((client, channel)),
+                // The server may allow fewer sessions than assumed, or the failure was
+                // transient; skip this connection for a while instead of for good
+                Err(_) => pool.lock().await.refused(&client),
+            }
+        }
+
+        let host_port = parse_host_port(&project.host);
+        let auth = build_auth_method(
+            &project.auth_method,
+            project.password.as_deref(),
+            project.key_file.as_deref(),
+            project.public_key_file.as_deref(),
+        )?;
+        let client = Arc::new(
+            connect_to_ssh(&host_port.hostname, host_port.port, &project.user, auth, 10).await?,
+        );
+
+        let channel = match client.get_channel().await {
+            Ok(channel) => channel,
+            Err(e) => {
+                let _ = client.disconnect().await;
+                return Err(format!("Failed to open channel: {}", e));
+            }
+        };
+
+        let mut pool = pool.lock().await;
+        let limit = pool.max_sessions;
+        pool.entries.push(PoolEntry {
+            client: Arc::clone(&client),
+            channels: 1,
+            limit,
+            retry_after: None,
+            owned: true,
+        });
+
+        Ok((client, channel))
+    }
+
+    // Takes a slot on the first usable connection, or returns None when all are full
+    fn reserve(&mut self) -> Option<Arc<Client>> {
+        self.entries.retain(|e| !e.owned || !e.client.is_closed());
+
+        let now = Instant::now();
+        let entry = self.entries.iter_mut().find(|e| {
+            e.channels < e.limit
+                && !e.client.is_closed()
+                && !e.retry_after.is_some_and(|t| now < t)
+        })?;
+
+        entry.channels += 1;
+        Some(Arc::clone(&entry.client))
+    }
+
+    // Gives back a slot taken by `reserve` whose channel the server refused
+    fn refused(&mut self, client: &Arc<Client>) {
+        if let Some(entry) = self.entries.iter_mut().find(|e| Arc::ptr_eq(&e.client, client)) {
+            entry.channels = entry.channels.saturating_sub(1);
+            entry.retry_after = Some(Instant::now() + CHANNEL_RETRY_DELAY);
+        }
+    }
+
+    /// Returns a channel slot; extra connections are closed once they carry no channels.
+    pub async fn release(&mut self, client: &Arc<Client>) {
+        let Some(index) = self.entries.iter().position(|e| Arc::ptr_eq(&e.client, client)) else {
+            return;
+        };
+
+        let entry = &mut self.entries[index];
+        entry.channels = entry.channels.saturating_sub(1);
+        // A freed slot is room the server is known to allow
+        entry.retry_after = None;
+
+        if entry.owned && entry.channels == 0 {
+            let entry = self.entries.remove(index);
+            let _ = entry.client.disconnect().await;
+        }
+    }
+
+    /// Disconnects every connection the pool opened; the main connection is left alone.
+    pub async fn disconnect_all(&mut self) {
+        for entry in self.entries.drain(..) {
+            if entry.owned {
+                let _ = entry.client.disconnect().await;
+            }
+        }
+    }
+}
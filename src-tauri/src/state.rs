@@ -1,9 +1,13 @@
-use crate::models::Project;
+use crate::models::{BroadcastMember, Project};
 use lazy_static::lazy_static;
+use std::collections::BTreeMap;
 use std::sync::{Arc, Mutex};
 
 lazy_static! {
     pub static ref PROJECTS: Mutex<Vec<Arc<Project>>> = Mutex::new(Vec::new());
+    /// Broadcast groups by name; members may belong to different projects
+    pub static ref BROADCAST_GROUPS: Mutex<BTreeMap<String, Vec<BroadcastMember>>> =
+        Mutex::new(BTreeMap::new());
 }
 
 pub fn get_project_by_key(key: &str) -> Result<Arc<Project>, String> {
@@ -32,5 +36,22 @@ pub fn remove_project_by_key(key: &str) -> Result<(), String> {
         .map_err(|e| format!("Failed to lock projects: {}", e))?;
 
     projects.retain(|project| project.key != key);
+    drop(projects);
+
+    remove_broadcast_members(key, None)
+}
+
+/// Removes a terminal, or every terminal of a project when `terminal_id` is `None`,
+/// from all broadcast groups. Groups left empty are dropped.
+pub fn remove_broadcast_members(key: &str, terminal_id: Option<&str>) -> Result<(), String> {
+    let mut groups = BROADCAST_GROUPS
+        .lock()
+        .map_err(|e| format!("Failed to lock broadcast groups: {}", e))?;
+
+    for members in groups.values_mut() {
+        members.retain(|m| m.key != key || terminal_id.is_some_and(|id| m.terminal_id != id));
+    }
+    groups.retain(|_, members| !members.is_empty());
+
     Ok(())
 }
\ No newline at end of file
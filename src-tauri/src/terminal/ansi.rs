@@ -0,0 +1,65 @@
+/// Removes ANSI escape sequences (CSI, OSC, DCS and two-byte escapes) from `text`.
+pub fn strip_ansi(text: &str) -> String {
+    strip_ansi_mapped(text).0
+}
+
+/// Like [`strip_ansi`], but also returns for every byte of the stripped text the
+/// index of the byte in `text` it came from.
+pub fn strip_ansi_mapped(text: &str) -> (String, Vec<usize>) {
+    let (output, source, _) = scan(text);
+    (output, source)
+}
+
+/// Strips a chunk of a stream. An escape sequence cut off at the end of `text` is left
+/// out, and its start is returned so the caller can prepend it to the next chunk.
+pub fn strip_ansi_partial(text: &str) -> (String, usize) {
+    let (output, _, unfinished) = scan(text);
+    (output, unfinished.unwrap_or(text.len()))
+}
+
+// Returns the stripped text, its source map and the start of an escape sequence
+// that was still open when the text ended
+fn scan(text: &str) -> (String, Vec<usize>, Option<usize>) {
+    let mut output = String::with_capacity(text.len());
+    let mut source = Vec::with_capacity(text.len());
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((index, c)) = chars.next() {
+        if c != '\u{1b}' {
+            output.push(c);
+            source.extend(index..index + c.len_utf8());
+            continue;
+        }
+
+        let finished = match chars.next().map(|(_, c)| c) {
+            // CSI: parameters and intermediates up to a final byte in @..~
+            Some('[') => chars.by_ref().any(|(_, c)| ('@'..='~').contains(&c)),
+            // OSC, DCS, SOS, PM, APC: terminated by BEL or ST (ESC \)
+            Some(']') | Some('P') | Some('X') | Some('^') | Some('_') => {
+                let mut terminated = false;
+                while let Some((_, c)) = chars.next() {
+                    if c == '\u{7}' {
+                        terminated = true;
+                        break;
+                    }
+                    if c == '\u{1b}' && chars.peek().map(|(_, c)| *c) == Some('\\') {
+                        chars.next();
+                        terminated = true;
+                        break;
+                    }
+                }
+                terminated
+            }
+            // Charset selection and similar escapes carry one more byte
+            Some('(') | Some(')') | Some('*') | Some('+') | Some('#') => chars.next().is_some(),
+            Some(_) => true,
+            None => false,
+        };
+
+        if !finished {
+            return (output, source, Some(index));
+        }
+    }
+
+    (output, source, None)
+}
@@ -0,0 +1,174 @@
+use std::collections::VecDeque;
+
+pub const DEFAULT_SCROLLBACK_BYTES: usize = 100_000;
+const MIN_SCROLLBACK_BYTES: usize = 4 * 1024;
+const MAX_SCROLLBACK_BYTES: usize = 16 * 1024 * 1024;
+
+/// Bounded buffer of raw terminal output.
+///
+/// Every byte ever written gets an absolute offset, so clients can ask for
+/// "everything since offset N" and learn whether older output was dropped.
+pub struct ScrollbackBuffer {
+    data: VecDeque<u8>,
+    capacity: usize,
+    start_offset: u64,
+    delivered_offset: u64,
+}
+
+/// Output between two offsets, decoded as UTF-8.
+pub struct ScrollbackChunk {
+    pub data: String,
+    pub offset: u64,
+    pub next_offset: u64,
+    pub truncated: bool,
+}
+
+impl ScrollbackBuffer {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.clamp(MIN_SCROLLBACK_BYTES, MAX_SCROLLBACK_BYTES);
+        Self {
+            // Grows with the output instead of reserving the whole capacity up front
+            data: VecDeque::new(),
+            capacity,
+            start_offset: 0,
+            delivered_offset: 0,
+        }
+    }
+
+    pub fn start_offset(&self) -> u64 {
+        self.start_offset
+    }
+
+    pub fn end_offset(&self) -> u64 {
+        self.start_offset + self.data.len() as u64
+    }
+
+    /// Appends raw output and returns the newly completed text since the previous push.
+    pub fn push(&mut self, bytes: &[u8]) -> ScrollbackChunk {
+        self.append(bytes);
+
+        let chunk = self.read_since(self.delivered_offset);
+        self.delivered_offset = chunk.next_offset;
+        chunk
+    }
+
+    fn append(&mut self, bytes: &[u8]) {
+        self.data.extend(bytes);
+
+        if self.data.len() > self.capacity {
+            let mut excess = self.data.len() - self.capacity;
+            // Never leave the buffer starting in the middle of a character
+            while excess < self.data.len() && is_continuation(self.data[excess]) {
+                excess += 1;
+            }
+            self.data.drain(..excess);
+            self.start_offset += excess as u64;
+        }
+    }
+
+    /// Drops all buffered output while keeping offsets monotonic.
+    pub fn clear(&mut self) {
+        self.start_offset = self.end_offset();
+        self.delivered_offset = self.delivered_offset.max(self.start_offset);
+        self.data.clear();
+    }
+
+    /// Returns the start offset and all buffered bytes, undecoded.
+    pub fn raw(&self) -> (u64, Vec<u8>) {
+        (self.start_offset, self.data.iter().copied().collect())
+    }
+
+    /// Returns the still-buffered output between two absolute offsets, decoded lossily.
+    pub fn read_range(&self, from: u64, to: u64) -> String {
+        let from = from.clamp(self.start_offset, self.end_offset()) - self.start_offset;
+        let to = to.clamp(self.start_offset, self.end_offset()) - self.start_offset;
+        if from >= to {
+            return String::new();
+        }
+
+        let bytes: Vec<u8> = self.data.range(from as usize..to as usize).copied().collect();
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+
+    /// Returns output from `offset` up to the last complete character.
+    /// A trailing partial character is left for the next read.
+    pub fn read_since(&self, offset: u64) -> ScrollbackChunk {
+        let truncated = offset < self.start_offset;
+        let mut from = offset.clamp(self.start_offset, self.end_offset()) - self.start_offset;
+        let mut from_index = from as usize;
+
+        while from_index < self.data.len() && is_continuation(self.data[from_index]) {
+            from_index += 1;
+        }
+        from = from_index as u64;
+
+        let to_index = self.data.len() - incomplete_tail_len(&self.data);
+        let to_index = to_index.max(from_index);
+
+        let bytes: Vec<u8> = self.data.range(from_index..to_index).copied().collect();
+
+        ScrollbackChunk {
+            data: String::from_utf8_lossy(&bytes).into_owned(),
+            offset: self.start_offset + from,
+            next_offset: self.start_offset + to_index as u64,
+            truncated,
+        }
+    }
+}
+
+/// Decodes output chunks that may end in the middle of a UTF-8 character.
+#[derive(Default)]
+pub struct Utf8Stream {
+    pending: Vec<u8>,
+}
+
+impl Utf8Stream {
+    pub fn push(&mut self, bytes: &[u8]) -> String {
+        self.pending.extend_from_slice(bytes);
+
+        let complete = match std::str::from_utf8(&self.pending) {
+            Ok(_) => self.pending.len(),
+            // Only an unfinished character at the very end is kept for the next chunk
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => self.pending.len(),
+        };
+
+        let text = String::from_utf8_lossy(&self.pending[..complete]).to_string();
+        self.pending.drain(..complete);
+        text
+    }
+
+    pub fn finish(&mut self) -> String {
+        let text = String::from_utf8_lossy(&self.pending).to_string();
+        self.pending.clear();
+        text
+    }
+}
+
+fn is_continuation(byte: u8) -> bool {
+    byte & 0b1100_0000 == 0b1000_0000
+}
+
+// Length of a UTF-8 sequence at the end of `data` that still misses bytes
+fn incomplete_tail_len(data: &VecDeque<u8>) -> usize {
+    let len = data.len();
+
+    for back in 1..=len.min(4) {
+        let byte = data[len - back];
+        if is_continuation(byte) {
+            continue;
+        }
+
+        let expected = match byte {
+            b if b & 0b1000_0000 == 0 => 1,
+            b if b & 0b1110_0000 == 0b1100_0000 => 2,
+            b if b & 0b1111_0000 == 0b1110_0000 => 3,
+            b if b & 0b1111_1000 == 0b1111_0000 => 4,
+            _ => return 0,
+        };
+
+        return if expected > back { back } else { 0 };
+    }
+
+    0
+}
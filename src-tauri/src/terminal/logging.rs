@@ -0,0 +1,125 @@
+use crate::terminal::ansi::strip_ansi_partial;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_LOG_MAX_BYTES: u64 = 10 * 1024 * 1024;
+pub const DEFAULT_LOG_MAX_FILES: usize = 5;
+// An escape sequence still open after this many bytes is broken and gets dropped
+const MAX_PENDING_ESCAPE: usize = 4096;
+
+/// Appends terminal output to a local log file, rotating it once it reaches
+/// `max_bytes`. Rotated files are kept as `name.1.log` (newest) to `name.N.log`.
+/// Writes are buffered; the caller flushes periodically and when logging stops.
+pub struct TerminalLogger {
+    path: PathBuf,
+    file: BufWriter<File>,
+    // Start of an escape sequence split across output chunks
+    pending: String,
+    written: u64,
+    max_bytes: u64,
+    max_files: usize,
+    strip_ansi: bool,
+}
+
+impl TerminalLogger {
+    pub fn create(path: &Path, strip_ansi: bool, max_bytes: u64, max_files: usize) -> Result<Self, String> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create log directory: {}", e))?;
+        }
+
+        let file = open_log(path)?;
+        let written = file.metadata().map(|m| m.len()).unwrap_or(0);
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            file: BufWriter::new(file),
+            pending: String::new(),
+            written,
+            max_bytes: max_bytes.max(1024),
+            max_files,
+            strip_ansi,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn write(&mut self, data: &str) -> Result<(), String> {
+        let data = if self.strip_ansi {
+            self.pending.push_str(data);
+            let (plain, end) = strip_ansi_partial(&self.pending);
+            self.pending.drain(..end);
+            if self.pending.len() > MAX_PENDING_ESCAPE {
+                self.pending.clear();
+            }
+            plain
+        } else {
+            data.to_string()
+        };
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        if self.written > 0 && self.written + data.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+
+        self.file
+            .write_all(data.as_bytes())
+            .map_err(|e| format!("Failed to write log: {}", e))?;
+        self.written += data.len() as u64;
+
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), String> {
+        self.file
+            .flush()
+            .map_err(|e| format!("Failed to write log: {}", e))
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let stem = self
+            .path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        self.path.with_file_name(format!("{}.{}.log", stem, index))
+    }
+
+    fn rotate(&mut self) -> Result<(), String> {
+        self.flush()?;
+
+        if self.max_files == 0 {
+            // Nothing is kept, so the current file just starts over
+            self.file = BufWriter::new(
+                File::create(&self.path).map_err(|e| format!("Failed to rotate log: {}", e))?,
+            );
+            self.written = 0;
+            return Ok(());
+        }
+
+        let _ = std::fs::remove_file(self.rotated_path(self.max_files));
+        for index in (1..self.max_files).rev() {
+            let _ = std::fs::rename(self.rotated_path(index), self.rotated_path(index + 1));
+        }
+        std::fs::rename(&self.path, self.rotated_path(1))
+            .map_err(|e| format!("Failed to rotate log: {}", e))?;
+
+        self.file = BufWriter::new(open_log(&self.path)?);
+        self.written = 0;
+
+        Ok(())
+    }
+}
+
+fn open_log(path: &Path) -> Result<File, String> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open log {}: {}", path.display(), e))
+}
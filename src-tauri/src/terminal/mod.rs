@@ -0,0 +1,11 @@
+pub mod ansi;
+pub mod buffer;
+pub mod logging;
+pub mod multiplexer;
+pub mod recording;
+pub mod screen;
+pub mod search;
+pub mod session;
+pub mod shell;
+pub mod shell_integration;
+pub mod sudo;
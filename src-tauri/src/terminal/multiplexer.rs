@@ -0,0 +1,140 @@
+use crate::paths::shell_quote;
+use async_ssh2_tokio::client::Client;
+use serde::Serialize;
+
+/// Terminal multiplexer used to keep shells alive on the server between connections.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Multiplexer {
+    Tmux,
+    Screen,
+}
+
+#[derive(Clone)]
+pub struct PersistentSession {
+    pub multiplexer: Multiplexer,
+    pub name: String,
+}
+
+#[derive(Serialize)]
+pub struct RemoteSession {
+    pub name: String,
+    pub multiplexer: String,
+    pub attached: bool,
+    pub created: Option<u64>,
+}
+
+impl Multiplexer {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "tmux" => Ok(Multiplexer::Tmux),
+            "screen" => Ok(Multiplexer::Screen),
+            _ => Err(format!("Unknown terminal multiplexer: {}", name)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Multiplexer::Tmux => "tmux",
+            Multiplexer::Screen => "screen",
+        }
+    }
+
+    /// Command that attaches to session `name`, creating it with `shell_command` if missing.
+    /// tmux drops the OSC sequences used by shell integration, so command records and
+    /// working directory tracking are unavailable inside tmux sessions.
+    pub fn attach_command(&self, name: &str, shell_command: &str) -> String {
+        match self {
+            Multiplexer::Tmux => format!(
+                "exec tmux new-session -A -s {} {}",
+                shell_quote(name),
+                shell_quote(shell_command)
+            ),
+            Multiplexer::Screen => format!(
+                "exec screen -D -R -S {} sh -c {}",
+                shell_quote(name),
+                shell_quote(shell_command)
+            ),
+        }
+    }
+
+    pub async fn kill(&self, connection: &Client, name: &str) -> Result<(), String> {
+        let command = match self {
+            Multiplexer::Tmux => format!("tmux kill-session -t {}", shell_quote(name)),
+            Multiplexer::Screen => format!("screen -S {} -X quit", shell_quote(name)),
+        };
+        run(connection, &command, "kill session").await
+    }
+}
+
+pub fn validate_session_name(name: &str) -> Result<(), String> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(format!(
+            "Invalid session name \"{}\": use letters, digits, '_' and '-'",
+            name
+        ));
+    }
+    Ok(())
+}
+
+async fn run(connection: &Client, command: &str, action: &str) -> Result<(), String> {
+    let result = connection
+        .execute(command)
+        .await
+        .map_err(|e| format!("Failed to {}: {}", action, e))?;
+
+    if result.exit_status == 0 {
+        Ok(())
+    } else {
+        Err(format!("Failed to {}: {}", action, result.stderr.trim()))
+    }
+}
+
+/// Lists tmux and screen sessions of the connected user. Missing multiplexers are skipped.
+pub async fn list_sessions(connection: &Client) -> Result<Vec<RemoteSession>, String> {
+    let mut sessions = Vec::new();
+
+    let tmux = connection
+        .execute("tmux list-sessions -F '#{session_name}|#{session_attached}|#{session_created}' 2>/dev/null")
+        .await
+        .map_err(|e| format!("Failed to list tmux sessions: {}", e))?;
+
+    for line in tmux.stdout.lines() {
+        let parts: Vec<&str> = line.splitn(3, '|').collect();
+        if parts.len() < 3 {
+            continue;
+        }
+        sessions.push(RemoteSession {
+            name: parts[0].to_string(),
+            multiplexer: Multiplexer::Tmux.name().to_string(),
+            attached: parts[1].parse::<u32>().unwrap_or(0) > 0,
+            created: parts[2].parse().ok(),
+        });
+    }
+
+    // screen -ls exits non-zero even when it lists sessions, so only the output matters
+    let screen = connection
+        .execute("screen -ls 2>/dev/null")
+        .await
+        .map_err(|e| format!("Failed to list screen sessions: {}", e))?;
+
+    for line in screen.stdout.lines() {
+        // "\t12345.name\t(Detached)"
+        let parts: Vec<&str> = line.trim().split('\t').collect();
+        if parts.len() < 2 || !parts[0].contains('.') {
+            continue;
+        }
+        let name = parts[0].split_once('.').map_or(parts[0], |(_, name)| name);
+        sessions.push(RemoteSession {
+            name: name.to_string(),
+            multiplexer: Multiplexer::Screen.name().to_string(),
+            attached: parts.iter().any(|p| p.contains("Attached")),
+            created: None,
+        });
+    }
+
+    Ok(sessions)
+}
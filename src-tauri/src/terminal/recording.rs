@@ -0,0 +1,165 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Writes terminal activity to an asciinema v2 `.cast` file.
+pub struct Recorder {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    started: Instant,
+    record_input: bool,
+}
+
+impl Recorder {
+    pub fn create(
+        path: &Path,
+        cols: u32,
+        rows: u32,
+        term: &str,
+        title: &str,
+        record_input: bool,
+    ) -> Result<Self, String> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create recording directory: {}", e))?;
+        }
+
+        // Never overwrites, so a recording cannot clobber an existing file
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .map_err(|e| format!("Failed to create recording: {}", e))?;
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        let header = json!({
+            "version": 2,
+            "width": cols,
+            "height": rows,
+            "timestamp": timestamp,
+            "title": title,
+            "env": { "TERM": term },
+        });
+
+        let mut recorder = Self {
+            path: path.to_path_buf(),
+            writer: BufWriter::new(file),
+            started: Instant::now(),
+            record_input,
+        };
+        recorder.write_line(&header.to_string())?;
+
+        Ok(recorder)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn output(&mut self, data: &str) -> Result<(), String> {
+        self.event("o", data)
+    }
+
+    /// Records typed input when the recording was started with input enabled.
+    pub fn input(&mut self, data: &str) -> Result<(), String> {
+        if !self.record_input {
+            return Ok(());
+        }
+        self.event("i", data)
+    }
+
+    pub fn resize(&mut self, cols: u32, rows: u32) -> Result<(), String> {
+        self.event("r", &format!("{}x{}", cols, rows))
+    }
+
+    fn event(&mut self, kind: &str, data: &str) -> Result<(), String> {
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        // Microsecond precision is what asciinema itself writes
+        let time = (self.started.elapsed().as_secs_f64() * 1_000_000.0).round() / 1_000_000.0;
+        let line = json!([time, kind, data]);
+        self.write_line(&line.to_string())
+    }
+
+    /// Writes buffered events to disk; called periodically and when recording stops.
+    pub fn flush(&mut self) -> Result<(), String> {
+        self.writer
+            .flush()
+            .map_err(|e| format!("Failed to write recording: {}", e))
+    }
+
+    fn write_line(&mut self, line: &str) -> Result<(), String> {
+        writeln!(self.writer, "{}", line).map_err(|e| format!("Failed to write recording: {}", e))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RecordingHeader {
+    pub version: u32,
+    pub width: u32,
+    pub height: u32,
+    #[serde(default)]
+    pub timestamp: Option<u64>,
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
+/// A single event of a recording: "o" for output, "i" for input, "r" for a resize to "COLSxROWS".
+#[derive(Serialize)]
+pub struct RecordingFrame {
+    pub time: f64,
+    pub kind: String,
+    pub data: String,
+}
+
+#[derive(Serialize)]
+pub struct Recording {
+    pub header: RecordingHeader,
+    pub frames: Vec<RecordingFrame>,
+    pub duration: f64,
+}
+
+/// Reads an asciinema v2 file back into its header and frames for replay.
+pub fn read_recording(path: &Path) -> Result<Recording, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open recording: {}", e))?;
+    let mut lines = BufReader::new(file).lines();
+
+    let header_line = lines
+        .next()
+        .ok_or_else(|| "Recording is empty".to_string())?
+        .map_err(|e| format!("Failed to read recording: {}", e))?;
+    let header: RecordingHeader = serde_json::from_str(&header_line)
+        .map_err(|e| format!("Invalid recording header: {}", e))?;
+
+    if header.version != 2 {
+        return Err(format!("Unsupported recording version: {}", header.version));
+    }
+
+    let mut frames = Vec::new();
+    for (index, line) in lines.enumerate() {
+        let line = line.map_err(|e| format!("Failed to read recording: {}", e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let (time, kind, data): (f64, String, String) = serde_json::from_str(&line)
+            .map_err(|e| format!("Invalid recording event on line {}: {}", index + 2, e))?;
+        frames.push(RecordingFrame { time, kind, data });
+    }
+
+    let duration = frames.last().map(|f| f.time).unwrap_or(0.0);
+
+    Ok(Recording {
+        header,
+        frames,
+        duration,
+    })
+}
@@ -0,0 +1,691 @@
+use crate::terminal::buffer::Utf8Stream;
+use serde::Serialize;
+use std::fmt::Write;
+
+const TAB_WIDTH: usize = 8;
+
+#[derive(Clone, Copy, PartialEq, Default)]
+enum Color {
+    #[default]
+    Default,
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+#[derive(Clone, Copy, PartialEq, Default)]
+struct Style {
+    fg: Color,
+    bg: Color,
+    bold: bool,
+    dim: bool,
+    italic: bool,
+    underline: bool,
+    blink: bool,
+    inverse: bool,
+    hidden: bool,
+    strikethrough: bool,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct Cell {
+    ch: char,
+    style: Style,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self {
+            ch: ' ',
+            style: Style::default(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct SavedCursor {
+    row: usize,
+    col: usize,
+    style: Style,
+}
+
+enum State {
+    Ground,
+    Escape,
+    // ESC ( and friends select a charset with the next character, which is ignored
+    EscapeCharset,
+    Csi,
+    // OSC, DCS and the other string sequences, terminated by BEL or ST
+    Text,
+    TextEscape,
+}
+
+/// The current screen contents as a client can draw them right away.
+#[derive(Serialize)]
+pub struct ScreenSnapshot {
+    pub cols: usize,
+    pub rows: usize,
+    /// Visible rows as plain text, trailing blanks removed
+    pub lines: Vec<String>,
+    pub cursor_row: usize,
+    pub cursor_col: usize,
+    pub cursor_visible: bool,
+    pub alternate_screen: bool,
+    /// Escape sequences that redraw this screen, styles and cursor included, on a fresh terminal
+    pub ansi: String,
+}
+
+/// VT100/xterm screen model fed with the terminal's output, so the screen of
+/// full-screen programs can be restored without replaying the scrollback.
+///
+/// Every character takes one cell; wide characters are not given two columns.
+pub struct Screen {
+    cols: usize,
+    rows: usize,
+    grid: Vec<Vec<Cell>>,
+    // Primary screen contents while the alternate screen is shown
+    primary: Option<Vec<Vec<Cell>>>,
+    cursor_row: usize,
+    cursor_col: usize,
+    // Set after writing the last column; the next character wraps first
+    wrap_pending: bool,
+    style: Style,
+    saved_cursor: SavedCursor,
+    scroll_top: usize,
+    scroll_bottom: usize,
+    autowrap: bool,
+    cursor_visible: bool,
+    last_char: Option<char>,
+    state: State,
+    params: String,
+    private_marker: Option<char>,
+    intermediates: String,
+    decoder: Utf8Stream,
+}
+
+impl Screen {
+    pub fn new(cols: u32, rows: u32) -> Self {
+        let cols = (cols as usize).max(1);
+        let rows = (rows as usize).max(1);
+
+        Self {
+            cols,
+            rows,
+            grid: vec![vec![Cell::default(); cols]; rows],
+            primary: None,
+            cursor_row: 0,
+            cursor_col: 0,
+            wrap_pending: false,
+            style: Style::default(),
+            saved_cursor: SavedCursor::default(),
+            scroll_top: 0,
+            scroll_bottom: rows - 1,
+            autowrap: true,
+            cursor_visible: true,
+            last_char: None,
+            state: State::Ground,
+            params: String::new(),
+            private_marker: None,
+            intermediates: String::new(),
+            decoder: Utf8Stream::default(),
+        }
+    }
+
+    pub fn feed(&mut self, bytes: &[u8]) {
+        let text = self.decoder.push(bytes);
+        for c in text.chars() {
+            self.process(c);
+        }
+    }
+
+    /// Changes the screen size, keeping the rows around the cursor.
+    pub fn resize(&mut self, cols: u32, rows: u32) {
+        let cols = (cols as usize).max(1);
+        let rows = (rows as usize).max(1);
+
+        // Rows above the cursor go first, as if they scrolled off the top
+        let dropped = (self.cursor_row + 1).saturating_sub(rows);
+        resize_grid(&mut self.grid, cols, rows, dropped);
+        if let Some(primary) = self.primary.as_mut() {
+            resize_grid(primary, cols, rows, dropped);
+        }
+
+        self.cols = cols;
+        self.rows = rows;
+        self.cursor_row -= dropped;
+        self.cursor_col = self.cursor_col.min(cols - 1);
+        self.wrap_pending = false;
+        self.scroll_top = 0;
+        self.scroll_bottom = rows - 1;
+        self.saved_cursor.row = self.saved_cursor.row.min(rows - 1);
+        self.saved_cursor.col = self.saved_cursor.col.min(cols - 1);
+    }
+
+    pub fn snapshot(&self) -> ScreenSnapshot {
+        ScreenSnapshot {
+            cols: self.cols,
+            rows: self.rows,
+            lines: self
+                .grid
+                .iter()
+                .map(|row| row.iter().map(|c| c.ch).collect::<String>().trim_end().to_string())
+                .collect(),
+            cursor_row: self.cursor_row,
+            cursor_col: self.cursor_col,
+            cursor_visible: self.cursor_visible,
+            alternate_screen: self.primary.is_some(),
+            ansi: self.render_ansi(),
+        }
+    }
+
+    fn render_ansi(&self) -> String {
+        let mut out = String::new();
+
+        if self.primary.is_some() {
+            out.push_str("\x1b[?1049h");
+        }
+        out.push_str("\x1b[0m\x1b[H\x1b[2J");
+
+        for (index, row) in self.grid.iter().enumerate() {
+            let Some(end) = row.iter().rposition(|c| *c != Cell::default()) else {
+                continue;
+            };
+
+            let _ = write!(out, "\x1b[{};1H", index + 1);
+            let mut current = Style::default();
+            for cell in &row[..=end] {
+                if cell.style != current {
+                    out.push_str(&sgr_sequence(&cell.style));
+                    current = cell.style;
+                }
+                out.push(cell.ch);
+            }
+            if current != Style::default() {
+                out.push_str("\x1b[0m");
+            }
+        }
+
+        if self.scroll_top != 0 || self.scroll_bottom != self.rows - 1 {
+            let _ = write!(out, "\x1b[{};{}r", self.scroll_top + 1, self.scroll_bottom + 1);
+        }
+        out.push_str(&sgr_sequence(&self.style));
+        let _ = write!(out, "\x1b[{};{}H", self.cursor_row + 1, self.cursor_col + 1);
+        out.push_str(if self.cursor_visible { "\x1b[?25h" } else { "\x1b[?25l" });
+
+        out
+    }
+
+    fn process(&mut self, c: char) {
+        match self.state {
+            State::Ground => self.ground(c),
+            State::Escape => self.escape(c),
+            State::EscapeCharset => self.state = State::Ground,
+            State::Csi => match c {
+                '0'..='9' | ';' | ':' => self.params.push(c),
+                '?' | '>' | '=' | '<' if self.params.is_empty() => self.private_marker = Some(c),
+                ' '..='/' => self.intermediates.push(c),
+                '@'..='~' => {
+                    self.state = State::Ground;
+                    self.dispatch_csi(c);
+                }
+                '\u{1b}' => self.state = State::Escape,
+                _ => {}
+            },
+            State::Text => match c {
+                '\u{7}' => self.state = State::Ground,
+                '\u{1b}' => self.state = State::TextEscape,
+                _ => {}
+            },
+            State::TextEscape => self.state = State::Ground,
+        }
+    }
+
+    fn ground(&mut self, c: char) {
+        match c {
+            '\u{1b}' => self.state = State::Escape,
+            '\u{8}' => {
+                self.cursor_col = self.cursor_col.saturating_sub(1);
+                self.wrap_pending = false;
+            }
+            '\t' => {
+                self.cursor_col = ((self.cursor_col / TAB_WIDTH + 1) * TAB_WIDTH).min(self.cols - 1);
+                self.wrap_pending = false;
+            }
+            '\n' | '\u{b}' | '\u{c}' => self.linefeed(),
+            '\r' => {
+                self.cursor_col = 0;
+                self.wrap_pending = false;
+            }
+            c if c < ' ' || c == '\u{7f}' => {}
+            c => self.print(c),
+        }
+    }
+
+    fn escape(&mut self, c: char) {
+        self.state = State::Ground;
+
+        match c {
+            '[' => {
+                self.params.clear();
+                self.intermediates.clear();
+                self.private_marker = None;
+                self.state = State::Csi;
+            }
+            ']' | 'P' | 'X' | '^' | '_' => self.state = State::Text,
+            '(' | ')' | '*' | '+' | '#' | '%' => self.state = State::EscapeCharset,
+            '7' => self.save_cursor(),
+            '8' => self.restore_cursor(),
+            'D' => self.linefeed(),
+            'E' => {
+                self.cursor_col = 0;
+                self.linefeed();
+            }
+            'M' => self.reverse_index(),
+            'c' => {
+                let (cols, rows) = (self.cols as u32, self.rows as u32);
+                *self = Screen::new(cols, rows);
+            }
+            _ => {}
+        }
+    }
+
+    fn print(&mut self, c: char) {
+        if self.wrap_pending && self.autowrap {
+            self.cursor_col = 0;
+            self.linefeed();
+        }
+
+        self.grid[self.cursor_row][self.cursor_col] = Cell {
+            ch: c,
+            style: self.style,
+        };
+        self.last_char = Some(c);
+
+        if self.cursor_col + 1 >= self.cols {
+            self.wrap_pending = true;
+        } else {
+            self.cursor_col += 1;
+            self.wrap_pending = false;
+        }
+    }
+
+    // Cleared cells take the current background, like xterm
+    fn blank(&self) -> Cell {
+        Cell {
+            ch: ' ',
+            style: Style {
+                bg: self.style.bg,
+                ..Style::default()
+            },
+        }
+    }
+
+    fn blank_row(&self) -> Vec<Cell> {
+        vec![self.blank(); self.cols]
+    }
+
+    fn linefeed(&mut self) {
+        self.wrap_pending = false;
+        if self.cursor_row == self.scroll_bottom {
+            self.scroll_up(1);
+        } else if self.cursor_row + 1 < self.rows {
+            self.cursor_row += 1;
+        }
+    }
+
+    fn reverse_index(&mut self) {
+        self.wrap_pending = false;
+        if self.cursor_row == self.scroll_top {
+            self.scroll_down(1);
+        } else if self.cursor_row > 0 {
+            self.cursor_row -= 1;
+        }
+    }
+
+    fn scroll_up(&mut self, count: usize) {
+        let count = count.min(self.scroll_bottom - self.scroll_top + 1);
+        for _ in 0..count {
+            self.grid.remove(self.scroll_top);
+            let row = self.blank_row();
+            self.grid.insert(self.scroll_bottom, row);
+        }
+    }
+
+    fn scroll_down(&mut self, count: usize) {
+        let count = count.min(self.scroll_bottom - self.scroll_top + 1);
+        for _ in 0..count {
+            self.grid.remove(self.scroll_bottom);
+            let row = self.blank_row();
+            self.grid.insert(self.scroll_top, row);
+        }
+    }
+
+    fn save_cursor(&mut self) {
+        self.saved_cursor = SavedCursor {
+            row: self.cursor_row,
+            col: self.cursor_col,
+            style: self.style,
+        };
+    }
+
+    fn restore_cursor(&mut self) {
+        self.cursor_row = self.saved_cursor.row.min(self.rows - 1);
+        self.cursor_col = self.saved_cursor.col.min(self.cols - 1);
+        self.style = self.saved_cursor.style;
+        self.wrap_pending = false;
+    }
+
+    fn enter_alternate_screen(&mut self) {
+        if self.primary.is_none() {
+            let alternate = vec![vec![Cell::default(); self.cols]; self.rows];
+            self.primary = Some(std::mem::replace(&mut self.grid, alternate));
+        }
+    }
+
+    fn leave_alternate_screen(&mut self) {
+        if let Some(primary) = self.primary.take() {
+            self.grid = primary;
+        }
+    }
+
+    fn parse_params(&self) -> Vec<u16> {
+        if self.params.is_empty() {
+            return Vec::new();
+        }
+
+        self.params
+            .split([';', ':'])
+            .map(|p| p.parse::<u16>().unwrap_or(0))
+            .collect()
+    }
+
+    fn dispatch_csi(&mut self, action: char) {
+        let params = self.parse_params();
+        // Missing and zero parameters both mean the default for most sequences
+        let param = |index: usize, default: usize| match params.get(index) {
+            Some(&value) if value > 0 => value as usize,
+            _ => default,
+        };
+
+        if self.private_marker == Some('?') {
+            if action == 'h' || action == 'l' {
+                for &mode in &params {
+                    self.set_private_mode(mode, action == 'h');
+                }
+            }
+            return;
+        }
+        if self.private_marker.is_some() || !self.intermediates.is_empty() {
+            return;
+        }
+
+        if action != 'm' {
+            self.wrap_pending = false;
+        }
+
+        let last_row = self.rows - 1;
+        let last_col = self.cols - 1;
+
+        match action {
+            'A' => self.cursor_row = self.cursor_row.saturating_sub(param(0, 1)),
+            'B' | 'e' => self.cursor_row = (self.cursor_row + param(0, 1)).min(last_row),
+            'C' | 'a' => self.cursor_col = (self.cursor_col + param(0, 1)).min(last_col),
+            'D' => self.cursor_col = self.cursor_col.saturating_sub(param(0, 1)),
+            'E' => {
+                self.cursor_row = (self.cursor_row + param(0, 1)).min(last_row);
+                self.cursor_col = 0;
+            }
+            'F' => {
+                self.cursor_row = self.cursor_row.saturating_sub(param(0, 1));
+                self.cursor_col = 0;
+            }
+            'G' | '`' => self.cursor_col = (param(0, 1) - 1).min(last_col),
+            'd' => self.cursor_row = (param(0, 1) - 1).min(last_row),
+            'H' | 'f' => {
+                self.cursor_row = (param(0, 1) - 1).min(last_row);
+                self.cursor_col = (param(1, 1) - 1).min(last_col);
+            }
+            'J' => self.erase_display(params.first().copied().unwrap_or(0)),
+            'K' => self.erase_line(params.first().copied().unwrap_or(0)),
+            'L' => self.insert_lines(param(0, 1)),
+            'M' => self.delete_lines(param(0, 1)),
+            'P' => {
+                let blank = self.blank();
+                let row = &mut self.grid[self.cursor_row];
+                for _ in 0..param(0, 1).min(self.cols - self.cursor_col) {
+                    row.remove(self.cursor_col);
+                    row.push(blank);
+                }
+            }
+            '@' => {
+                let blank = self.blank();
+                let row = &mut self.grid[self.cursor_row];
+                for _ in 0..param(0, 1).min(self.cols - self.cursor_col) {
+                    row.insert(self.cursor_col, blank);
+                    row.pop();
+                }
+            }
+            'X' => {
+                let blank = self.blank();
+                let end = (self.cursor_col + param(0, 1)).min(self.cols);
+                self.grid[self.cursor_row][self.cursor_col..end].fill(blank);
+            }
+            'S' => self.scroll_up(param(0, 1)),
+            'T' => self.scroll_down(param(0, 1)),
+            'b' => {
+                if let Some(c) = self.last_char {
+                    for _ in 0..param(0, 1).min(self.cols * self.rows) {
+                        self.print(c);
+                    }
+                }
+            }
+            'm' => self.select_graphic_rendition(&params),
+            'r' => {
+                let top = param(0, 1) - 1;
+                let bottom = param(1, self.rows).min(self.rows) - 1;
+                if top < bottom {
+                    self.scroll_top = top;
+                    self.scroll_bottom = bottom;
+                    self.cursor_row = 0;
+                    self.cursor_col = 0;
+                }
+            }
+            's' => self.save_cursor(),
+            'u' => self.restore_cursor(),
+            _ => {}
+        }
+    }
+
+    fn set_private_mode(&mut self, mode: u16, enabled: bool) {
+        match mode {
+            7 => self.autowrap = enabled,
+            25 => self.cursor_visible = enabled,
+            47 | 1047 => {
+                if enabled {
+                    self.enter_alternate_screen();
+                } else {
+                    self.leave_alternate_screen();
+                }
+            }
+            1049 => {
+                if enabled {
+                    self.save_cursor();
+                    self.enter_alternate_screen();
+                } else {
+                    self.leave_alternate_screen();
+                    self.restore_cursor();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn erase_display(&mut self, mode: u16) {
+        let blank = self.blank();
+        match mode {
+            0 => {
+                self.erase_line(0);
+                for row in &mut self.grid[self.cursor_row + 1..] {
+                    row.fill(blank);
+                }
+            }
+            1 => {
+                self.erase_line(1);
+                for row in &mut self.grid[..self.cursor_row] {
+                    row.fill(blank);
+                }
+            }
+            2 | 3 => {
+                for row in &mut self.grid {
+                    row.fill(blank);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn erase_line(&mut self, mode: u16) {
+        let blank = self.blank();
+        let col = self.cursor_col;
+        let row = &mut self.grid[self.cursor_row];
+        match mode {
+            0 => row[col..].fill(blank),
+            1 => row[..=col].fill(blank),
+            2 => row.fill(blank),
+            _ => {}
+        }
+    }
+
+    fn insert_lines(&mut self, count: usize) {
+        if self.cursor_row < self.scroll_top || self.cursor_row > self.scroll_bottom {
+            return;
+        }
+
+        for _ in 0..count.min(self.scroll_bottom - self.cursor_row + 1) {
+            self.grid.remove(self.scroll_bottom);
+            let row = self.blank_row();
+            self.grid.insert(self.cursor_row, row);
+        }
+        self.cursor_col = 0;
+    }
+
+    fn delete_lines(&mut self, count: usize) {
+        if self.cursor_row < self.scroll_top || self.cursor_row > self.scroll_bottom {
+            return;
+        }
+
+        for _ in 0..count.min(self.scroll_bottom - self.cursor_row + 1) {
+            self.grid.remove(self.cursor_row);
+            let row = self.blank_row();
+            self.grid.insert(self.scroll_bottom, row);
+        }
+        self.cursor_col = 0;
+    }
+
+    fn select_graphic_rendition(&mut self, params: &[u16]) {
+        if params.is_empty() {
+            self.style = Style::default();
+            return;
+        }
+
+        let mut index = 0;
+        while index < params.len() {
+            let style = &mut self.style;
+            match params[index] {
+                0 => *style = Style::default(),
+                1 => style.bold = true,
+                2 => style.dim = true,
+                3 => style.italic = true,
+                4 | 21 => style.underline = true,
+                5 | 6 => style.blink = true,
+                7 => style.inverse = true,
+                8 => style.hidden = true,
+                9 => style.strikethrough = true,
+                22 => {
+                    style.bold = false;
+                    style.dim = false;
+                }
+                23 => style.italic = false,
+                24 => style.underline = false,
+                25 => style.blink = false,
+                27 => style.inverse = false,
+                28 => style.hidden = false,
+                29 => style.strikethrough = false,
+                p @ 30..=37 => style.fg = Color::Indexed((p - 30) as u8),
+                38 => style.fg = extended_color(params, &mut index),
+                39 => style.fg = Color::Default,
+                p @ 40..=47 => style.bg = Color::Indexed((p - 40) as u8),
+                48 => style.bg = extended_color(params, &mut index),
+                49 => style.bg = Color::Default,
+                p @ 90..=97 => style.fg = Color::Indexed((p - 90 + 8) as u8),
+                p @ 100..=107 => style.bg = Color::Indexed((p - 100 + 8) as u8),
+                _ => {}
+            }
+            index += 1;
+        }
+    }
+}
+
+// Reads `5;n` or `2;r;g;b` after a 38/48 parameter, leaving `index` on its last part
+fn extended_color(params: &[u16], index: &mut usize) -> Color {
+    let part = |offset: usize| params.get(*index + offset).map(|&p| p.min(255) as u8);
+
+    match params.get(*index + 1) {
+        Some(5) => {
+            let color = part(2).map(Color::Indexed).unwrap_or_default();
+            *index += 2;
+            color
+        }
+        Some(2) => {
+            let color = match (part(2), part(3), part(4)) {
+                (Some(r), Some(g), Some(b)) => Color::Rgb(r, g, b),
+                _ => Color::Default,
+            };
+            *index += 4;
+            color
+        }
+        _ => Color::Default,
+    }
+}
+
+fn resize_grid(grid: &mut Vec<Vec<Cell>>, cols: usize, rows: usize, dropped: usize) {
+    grid.drain(..dropped.min(grid.len()));
+    grid.resize(rows, vec![Cell::default(); cols]);
+    for row in grid.iter_mut() {
+        row.resize(cols, Cell::default());
+    }
+}
+
+fn color_codes(color: &Color, base: u8, bright_base: u8, extended: u8) -> String {
+    match *color {
+        Color::Default => String::new(),
+        Color::Indexed(n) if n < 8 => format!(";{}", base + n),
+        Color::Indexed(n) if n < 16 => format!(";{}", bright_base + n - 8),
+        Color::Indexed(n) => format!(";{};5;{}", extended, n),
+        Color::Rgb(r, g, b) => format!(";{};2;{};{};{}", extended, r, g, b),
+    }
+}
+
+fn sgr_sequence(style: &Style) -> String {
+    let mut sequence = String::from("\x1b[0");
+
+    for (enabled, code) in [
+        (style.bold, "1"),
+        (style.dim, "2"),
+        (style.italic, "3"),
+        (style.underline, "4"),
+        (style.blink, "5"),
+        (style.inverse, "7"),
+        (style.hidden, "8"),
+        (style.strikethrough, "9"),
+    ] {
+        if enabled {
+            sequence.push(';');
+            sequence.push_str(code);
+        }
+    }
+
+    sequence.push_str(&color_codes(&style.fg, 30, 90, 38));
+    sequence.push_str(&color_codes(&style.bg, 40, 100, 48));
+    sequence.push('m');
+    sequence
+}
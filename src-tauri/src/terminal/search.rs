@@ -0,0 +1,94 @@
+use crate::terminal::ansi::strip_ansi_mapped;
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+
+/// A hit in the scrollback. Offsets are absolute scrollback offsets of the raw output,
+/// so escape sequences inside the match are part of the range.
+#[derive(Serialize)]
+pub struct ScrollbackMatch {
+    pub offset: u64,
+    pub end_offset: u64,
+    /// Matched text without escape sequences
+    pub text: String,
+    /// The line containing the start of the match, without escape sequences
+    pub line: String,
+}
+
+/// Builds the pattern for a search; plain queries are matched literally.
+pub fn build_pattern(query: &str, is_regex: bool, case_sensitive: bool) -> Result<Regex, String> {
+    if query.is_empty() {
+        return Err("Search query cannot be empty".to_string());
+    }
+
+    let pattern = if is_regex {
+        query.to_string()
+    } else {
+        regex::escape(query)
+    };
+
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!case_sensitive)
+        .build()
+        .map_err(|e| format!("Invalid search pattern: {}", e))
+}
+
+/// Finds up to `limit` matches of `pattern` in `raw` output starting at `base_offset`,
+/// matching against the text with escape sequences stripped. Also returns whether
+/// more matches were left out.
+pub fn search_output(
+    raw: &[u8],
+    base_offset: u64,
+    pattern: &Regex,
+    limit: usize,
+) -> (Vec<ScrollbackMatch>, bool) {
+    let (text, text_source) = decode_mapped(raw);
+    let (plain, plain_source) = strip_ansi_mapped(&text);
+    let source: Vec<usize> = plain_source.into_iter().map(|i| text_source[i]).collect();
+    let mut matches = Vec::new();
+
+    // Patterns that can match nothing would otherwise report a hit at every position
+    for found in pattern.find_iter(&plain).filter(|m| !m.is_empty()) {
+        if matches.len() == limit {
+            return (matches, true);
+        }
+
+        let line_start = plain[..found.start()].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = plain[found.end()..]
+            .find('\n')
+            .map_or(plain.len(), |i| found.end() + i);
+
+        matches.push(ScrollbackMatch {
+            offset: base_offset + source[found.start()] as u64,
+            end_offset: base_offset + source[found.end() - 1] as u64 + 1,
+            text: found.as_str().to_string(),
+            line: plain[line_start..line_end].trim_end_matches('\r').to_string(),
+        });
+    }
+
+    (matches, false)
+}
+
+// Decodes lossily like String::from_utf8_lossy, returning for every byte of the text
+// the index of the raw byte it came from. Offsets must stay byte-exact, so the three
+// bytes of a replacement character point back into the invalid bytes it stands for.
+fn decode_mapped(raw: &[u8]) -> (String, Vec<usize>) {
+    let mut text = String::with_capacity(raw.len());
+    let mut source = Vec::with_capacity(raw.len());
+    let mut index = 0;
+
+    for chunk in raw.utf8_chunks() {
+        let valid = chunk.valid();
+        text.push_str(valid);
+        source.extend(index..index + valid.len());
+        index += valid.len();
+
+        let invalid = chunk.invalid();
+        if !invalid.is_empty() {
+            text.push(char::REPLACEMENT_CHARACTER);
+            source.extend([index, index, index + invalid.len() - 1]);
+            index += invalid.len();
+        }
+    }
+
+    (text, source)
+}
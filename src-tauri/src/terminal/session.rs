@@ -0,0 +1,182 @@
+use crate::models::{TerminalControl, TerminalExit};
+use russh::client::Msg;
+use russh::{Channel, ChannelMsg, Sig};
+use tokio::sync::mpsc;
+
+pub const DEFAULT_TERM: &str = "xterm";
+pub const DEFAULT_COLS: u32 = 80;
+pub const DEFAULT_ROWS: u32 = 24;
+// Far beyond any real window; bigger sizes would only make the screen model allocate
+pub const MAX_COLS: u32 = 1000;
+pub const MAX_ROWS: u32 = 1000;
+
+#[derive(Clone)]
+pub struct PtyOptions {
+    pub term: String,
+    pub cols: u32,
+    pub rows: u32,
+}
+
+impl PtyOptions {
+    pub fn new(term: Option<String>, cols: Option<u32>, rows: Option<u32>) -> Self {
+        Self {
+            term: term
+                .filter(|t| !t.trim().is_empty())
+                .unwrap_or_else(|| DEFAULT_TERM.to_string()),
+            cols: cols.filter(|c| *c > 0).unwrap_or(DEFAULT_COLS).min(MAX_COLS),
+            rows: rows.filter(|r| *r > 0).unwrap_or(DEFAULT_ROWS).min(MAX_ROWS),
+        }
+    }
+}
+
+/// Parses a signal name such as "INT" or "SIGTERM".
+pub fn parse_signal(name: &str) -> Result<Sig, String> {
+    let name = name.trim().to_uppercase();
+    let name = name.strip_prefix("SIG").unwrap_or(&name);
+
+    match name {
+        "INT" => Ok(Sig::INT),
+        "TERM" => Ok(Sig::TERM),
+        "KILL" => Ok(Sig::KILL),
+        "HUP" => Ok(Sig::HUP),
+        "QUIT" => Ok(Sig::QUIT),
+        "USR1" => Ok(Sig::USR1),
+        "PIPE" => Ok(Sig::PIPE),
+        "ALRM" => Ok(Sig::ALRM),
+        _ => Err(format!("Unsupported signal: {}", name)),
+    }
+}
+
+pub fn signal_name(signal: &Sig) -> String {
+    match signal {
+        Sig::ABRT => "ABRT".to_string(),
+        Sig::ALRM => "ALRM".to_string(),
+        Sig::FPE => "FPE".to_string(),
+        Sig::HUP => "HUP".to_string(),
+        Sig::ILL => "ILL".to_string(),
+        Sig::INT => "INT".to_string(),
+        Sig::KILL => "KILL".to_string(),
+        Sig::PIPE => "PIPE".to_string(),
+        Sig::QUIT => "QUIT".to_string(),
+        Sig::SEGV => "SEGV".to_string(),
+        Sig::TERM => "TERM".to_string(),
+        Sig::USR1 => "USR1".to_string(),
+        Sig::Custom(name) => name.clone(),
+    }
+}
+
+// Control character the PTY turns into the same signal for the foreground job
+fn pty_control_char(signal: &Sig) -> Option<u8> {
+    match signal {
+        Sig::INT => Some(0x03),
+        Sig::QUIT => Some(0x1c),
+        _ => None,
+    }
+}
+
+/// Runs `command` on `channel` with a PTY attached, forwarding output to `stdout_tx`
+/// and stdin / control requests to the channel until the remote side closes it.
+/// Returns how the remote process ended.
+pub async fn run_pty_session(
+    mut channel: Channel<Msg>,
+    command: &str,
+    pty: &PtyOptions,
+    stdout_tx: mpsc::Sender<Vec<u8>>,
+    mut stdin_rx: mpsc::Receiver<Vec<u8>>,
+    mut control_rx: mpsc::Receiver<TerminalControl>,
+) -> Result<TerminalExit, String> {
+    channel
+        .request_pty(false, &pty.term, pty.cols, pty.rows, 0, 0, &[])
+        .await
+        .map_err(|e| format!("Failed to request PTY: {}", e))?;
+
+    channel
+        .exec(true, command)
+        .await
+        .map_err(|e| format!("Failed to start shell: {}", e))?;
+
+    let mut exit = TerminalExit::default();
+    let mut stdin_open = true;
+    let mut eof_sent = false;
+    let mut control_open = true;
+
+    loop {
+        tokio::select! {
+            msg = channel.wait() => match msg {
+                Some(ChannelMsg::Data { data }) | Some(ChannelMsg::ExtendedData { data, .. }) => {
+                    // The reader going away means nobody is interested in this shell anymore
+                    if stdout_tx.send(data.to_vec()).await.is_err() {
+                        let _ = channel.close().await;
+                        break;
+                    }
+                }
+                Some(ChannelMsg::ExitStatus { exit_status }) => {
+                    exit.exit_status = Some(exit_status);
+                }
+                Some(ChannelMsg::ExitSignal { signal_name: signal, core_dumped, error_message, .. }) => {
+                    exit.signal = Some(signal_name(&signal));
+                    exit.core_dumped = core_dumped;
+                    if !error_message.is_empty() {
+                        exit.error = Some(error_message);
+                    }
+                }
+                Some(ChannelMsg::Close) | None => break,
+                Some(_) => {}
+            },
+            input = stdin_rx.recv(), if stdin_open => match input {
+                // Input after EOF is dropped, but still drained so senders never block
+                Some(_) if eof_sent => {}
+                Some(bytes) => {
+                    channel
+                        .data(&bytes[..])
+                        .await
+                        .map_err(|e| format!("Failed to write to shell: {}", e))?;
+                }
+                None => {
+                    stdin_open = false;
+                    if !eof_sent {
+                        eof_sent = true;
+                        let _ = channel.eof().await;
+                    }
+                }
+            },
+            control = control_rx.recv(), if control_open => match control {
+                Some(TerminalControl::Resize { cols, rows }) => {
+                    channel
+                        .window_change(cols, rows, 0, 0)
+                        .await
+                        .map_err(|e| format!("Failed to resize terminal: {}", e))?;
+                }
+                Some(TerminalControl::Signal(signal)) => {
+                    // OpenSSH delivers channel signals to the shell itself, so signals
+                    // with a control character go through the PTY to the foreground job
+                    match pty_control_char(&signal) {
+                        Some(byte) => channel
+                            .data(&[byte][..])
+                            .await
+                            .map_err(|e| format!("Failed to write to shell: {}", e))?,
+                        None => channel
+                            .signal(signal)
+                            .await
+                            .map_err(|e| format!("Failed to send signal: {}", e))?,
+                    }
+                }
+                Some(TerminalControl::Eof) if eof_sent => {}
+                Some(TerminalControl::Eof) => {
+                    eof_sent = true;
+                    channel
+                        .eof()
+                        .await
+                        .map_err(|e| format!("Failed to send EOF: {}", e))?;
+                }
+                Some(TerminalControl::Close) => {
+                    let _ = channel.close().await;
+                    break;
+                }
+                None => control_open = false,
+            },
+        }
+    }
+
+    Ok(exit)
+}
@@ -0,0 +1,99 @@
+use crate::paths::{posix_script, shell_quote};
+use crate::terminal::shell_integration;
+use async_ssh2_tokio::client::Client;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+const KNOWN_SHELLS: &[&str] = &["bash", "zsh", "fish", "ksh", "mksh", "dash", "ash", "sh"];
+
+/// How the interactive shell of a terminal is started.
+pub struct ShellSpec {
+    /// Shell name or path; `None` uses the user's login shell
+    pub shell: Option<String>,
+    pub login: bool,
+    pub environment: BTreeMap<String, String>,
+}
+
+#[derive(Serialize)]
+pub struct ShellInfo {
+    pub name: String,
+    pub path: String,
+    pub is_default: bool,
+}
+
+/// Builds the command that enters `path` and replaces itself with the configured shell,
+/// using shell integration where the shell supports it.
+pub async fn launch_command(connection: &Client, spec: &ShellSpec, path: &str) -> Result<String, String> {
+    let shell = match &spec.shell {
+        Some(shell) => shell.clone(),
+        None => default_shell(connection).await?,
+    };
+
+    let mut command = format!("cd {} || exit 1; ", shell_quote(path));
+
+    for (name, value) in &spec.environment {
+        if !is_valid_env_name(name) {
+            return Err(format!("Invalid environment variable name: {}", name));
+        }
+        command.push_str(&format!("export {}={}; ", name, shell_quote(value)));
+    }
+
+    // Shells without integration still start, they just report no command boundaries
+    match shell_integration::install(connection, &shell, spec.login).await {
+        Ok(Some(launch)) => command.push_str(&launch),
+        _ => command.push_str(&format!(
+            "exec {} {}",
+            shell_quote(&shell),
+            if spec.login { "-l" } else { "-i" }
+        )),
+    }
+
+    Ok(posix_script(&command))
+}
+
+async fn default_shell(connection: &Client) -> Result<String, String> {
+    let result = connection
+        .execute(&posix_script("printf '%s' \"${SHELL:-/bin/sh}\""))
+        .await
+        .map_err(|e| format!("Failed to detect login shell: {}", e))?;
+
+    let shell = result.stdout.trim();
+    Ok(if shell.is_empty() { "/bin/sh" } else { shell }.to_string())
+}
+
+/// Lists shells installed on the server, marking the user's login shell.
+pub async fn detect_shells(connection: &Client) -> Result<Vec<ShellInfo>, String> {
+    let default = default_shell(connection).await?;
+
+    let command = format!(
+        "{{ grep '^/' /etc/shells 2>/dev/null; for s in {}; do command -v \"$s\"; done; }} | \
+         while read -r s; do [ -x \"$s\" ] && echo \"$s\"; done",
+        KNOWN_SHELLS.join(" ")
+    );
+    let result = connection
+        .execute(&posix_script(&command))
+        .await
+        .map_err(|e| format!("Failed to detect shells: {}", e))?;
+
+    let mut shells: Vec<ShellInfo> = Vec::new();
+    for path in result.stdout.lines().map(str::trim).filter(|p| p.starts_with('/')) {
+        let name = path.rsplit('/').next().unwrap_or(path).to_string();
+        // /etc/shells often lists the same shell under /bin and /usr/bin
+        if shells.iter().any(|s| s.name == name) {
+            continue;
+        }
+        shells.push(ShellInfo {
+            is_default: path == default || default.ends_with(&format!("/{}", name)),
+            name,
+            path: path.to_string(),
+        });
+    }
+
+    Ok(shells)
+}
+
+fn is_valid_env_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
@@ -0,0 +1,325 @@
+use crate::paths::{posix_script, shell_quote};
+use async_ssh2_tokio::client::Client;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const MAX_OSC_LEN: usize = 4096;
+const MAX_COMMAND_RECORDS: usize = 500;
+
+/// Remote directory holding the generated startup files.
+const INTEGRATION_DIR: &str = "${XDG_CACHE_HOME:-$HOME/.cache}/remote-explorer";
+
+// Sourced instead of ~/.bashrc: loads the user's config (the profile files for login
+// shells), then reports prompt (133;A/B), command start (133;C), exit status (133;D)
+// and working directory (OSC 7)
+const BASH_RC: &str = r#"if [ -n "$__RE_LOGIN" ]; then
+    unset __RE_LOGIN
+    [ -f /etc/profile ] && . /etc/profile
+    if [ -f ~/.bash_profile ]; then . ~/.bash_profile
+    elif [ -f ~/.bash_login ]; then . ~/.bash_login
+    elif [ -f ~/.profile ]; then . ~/.profile
+    fi
+else
+    [ -f ~/.bashrc ] && . ~/.bashrc
+fi
+__re_prompt_start() {
+    local ret=$?
+    if [ -n "$__re_ran" ]; then
+        printf '\033]133;D;%s\007' "$ret"
+    fi
+    __re_ran=
+    return $ret
+}
+__re_prompt_end() {
+    printf '\033]7;file://%s%s\007' "$HOSTNAME" "$PWD"
+    printf '\033]133;A\007'
+    case "$PS1" in
+        *'133;B'*) ;;
+        *) PS1="$PS1"'\[\033]133;B\007\]' ;;
+    esac
+}
+PROMPT_COMMAND="__re_prompt_start${PROMPT_COMMAND:+;$PROMPT_COMMAND};__re_prompt_end"
+PS0='\033]133;C\007${__re_noop[__re_ran=1]}'
+"#;
+
+// zsh reads these from ZDOTDIR; they chain to the user's own files first.
+// .zlogin is read after .zshrc has restored ZDOTDIR, so it needs no wrapper.
+const ZSH_ENV: &str = r#"[ -f "$HOME/.zshenv" ] && . "$HOME/.zshenv"
+"#;
+
+const ZSH_PROFILE: &str = r#"[ -f "$HOME/.zprofile" ] && . "$HOME/.zprofile"
+"#;
+
+const ZSH_RC: &str = r#"ZDOTDIR="$HOME"
+[ -f "$HOME/.zshrc" ] && . "$HOME/.zshrc"
+__re_precmd() {
+    local ret=$?
+    if [ -n "$__re_ran" ]; then
+        printf '\033]133;D;%s\007' "$ret"
+    fi
+    __re_ran=
+    printf '\033]7;file://%s%s\007' "$HOST" "$PWD"
+    printf '\033]133;A\007'
+    case "$PS1" in
+        *'133;B'*) ;;
+        *) PS1="$PS1"$'%{\033]133;B\007%}' ;;
+    esac
+}
+__re_preexec() {
+    __re_ran=1
+    printf '\033]133;C\007'
+}
+autoload -Uz add-zsh-hook
+add-zsh-hook precmd __re_precmd
+add-zsh-hook preexec __re_preexec
+"#;
+
+/// Writes the startup files to the server and returns the command that starts
+/// `shell` with them, or `None` when the shell has no integration.
+pub async fn install(connection: &Client, shell: &str, login: bool) -> Result<Option<String>, String> {
+    let shell_name = shell.rsplit('/').next().unwrap_or(shell);
+
+    let (files, launch): (Vec<(&str, &str)>, String) = match shell_name {
+        // bash ignores --rcfile in login mode, so the rc file loads the profile itself
+        "bash" => (
+            vec![("bashrc", BASH_RC)],
+            format!(
+                "{}exec {} --rcfile \"$dir/bashrc\" -i",
+                if login { "export __RE_LOGIN=1; " } else { "" },
+                shell_quote(shell)
+            ),
+        ),
+        "zsh" => (
+            vec![
+                ("zsh/.zshenv", ZSH_ENV),
+                ("zsh/.zprofile", ZSH_PROFILE),
+                ("zsh/.zshrc", ZSH_RC),
+            ],
+            format!(
+                "export ZDOTDIR=\"$dir/zsh\"; exec {} {}-i",
+                shell_quote(shell),
+                if login { "-l " } else { "" }
+            ),
+        ),
+        _ => return Ok(None),
+    };
+
+    let mut script = format!("dir=\"{}\" && mkdir -p \"$dir/zsh\"", INTEGRATION_DIR);
+    for (name, content) in files {
+        script.push_str(&format!(
+            " && printf '%s' {} > \"$dir/{}\"",
+            shell_quote(content),
+            name
+        ));
+    }
+
+    let result = connection
+        .execute(&posix_script(&script))
+        .await
+        .map_err(|e| format!("Failed to install shell integration: {}", e))?;
+
+    if result.exit_status != 0 {
+        return Err(format!(
+            "Failed to install shell integration: {}",
+            result.stderr.trim()
+        ));
+    }
+
+    Ok(Some(format!("dir=\"{}\"; {}", INTEGRATION_DIR, launch)))
+}
+
+pub enum ShellEvent {
+    PromptStart,
+    CommandInputStart,
+    CommandExecuted,
+    CommandFinished(Option<i32>),
+    WorkingDirectory(String),
+}
+
+#[derive(Default)]
+enum ScanState {
+    #[default]
+    Normal,
+    Escape,
+    Osc,
+    OscEscape,
+}
+
+/// Finds OSC 133 and OSC 7 sequences in raw output, including ones split across chunks.
+#[derive(Default)]
+pub struct OscScanner {
+    state: ScanState,
+    payload: Vec<u8>,
+}
+
+impl OscScanner {
+    /// Returns events with the index just past their terminator in `data`.
+    pub fn feed(&mut self, data: &[u8]) -> Vec<(usize, ShellEvent)> {
+        let mut events = Vec::new();
+
+        for (index, &byte) in data.iter().enumerate() {
+            self.state = match std::mem::take(&mut self.state) {
+                ScanState::Normal if byte == 0x1b => ScanState::Escape,
+                ScanState::Normal => ScanState::Normal,
+                ScanState::Escape if byte == b']' => {
+                    self.payload.clear();
+                    ScanState::Osc
+                }
+                ScanState::Escape if byte == 0x1b => ScanState::Escape,
+                ScanState::Escape => ScanState::Normal,
+                ScanState::Osc | ScanState::OscEscape if byte == 0x07 => {
+                    events.extend(parse_osc(&self.payload).map(|e| (index + 1, e)));
+                    ScanState::Normal
+                }
+                ScanState::Osc if byte == 0x1b => ScanState::OscEscape,
+                ScanState::Osc if self.payload.len() >= MAX_OSC_LEN => ScanState::Normal,
+                ScanState::Osc => {
+                    self.payload.push(byte);
+                    ScanState::Osc
+                }
+                ScanState::OscEscape if byte == b'\\' => {
+                    events.extend(parse_osc(&self.payload).map(|e| (index + 1, e)));
+                    ScanState::Normal
+                }
+                ScanState::OscEscape => ScanState::Normal,
+            };
+        }
+
+        events
+    }
+}
+
+fn parse_osc(payload: &[u8]) -> Option<ShellEvent> {
+    let payload = String::from_utf8_lossy(payload);
+    let mut parts = payload.splitn(3, ';');
+
+    match (parts.next()?, parts.next()) {
+        ("133", Some("A")) => Some(ShellEvent::PromptStart),
+        ("133", Some("B")) => Some(ShellEvent::CommandInputStart),
+        ("133", Some("C")) => Some(ShellEvent::CommandExecuted),
+        ("133", Some("D")) => Some(ShellEvent::CommandFinished(
+            parts.next().and_then(|code| code.trim().parse().ok()),
+        )),
+        ("7", Some(url)) => parse_file_url(url).map(ShellEvent::WorkingDirectory),
+        _ => None,
+    }
+}
+
+// "file://host/some%20dir" -> "/some dir"
+fn parse_file_url(url: &str) -> Option<String> {
+    let rest = url.strip_prefix("file://")?;
+    let path = &rest[rest.find('/')?..];
+
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let high = (bytes[i + 1] as char).to_digit(16);
+            let low = (bytes[i + 2] as char).to_digit(16);
+            if let (Some(high), Some(low)) = (high, low) {
+                decoded.push((high * 16 + low) as u8);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+
+    Some(String::from_utf8_lossy(&decoded).into_owned())
+}
+
+#[derive(Clone, Serialize)]
+pub struct CommandRecord {
+    pub id: u64,
+    pub command: String,
+    pub cwd: Option<String>,
+    pub exit_code: Option<i32>,
+    pub started_at: u64,
+    pub finished_at: Option<u64>,
+    pub output_offset: u64,
+    pub output_end_offset: Option<u64>,
+}
+
+/// Turns shell integration events into per-command records.
+#[derive(Default)]
+pub struct CommandTracker {
+    pub records: VecDeque<CommandRecord>,
+    pub current: Option<CommandRecord>,
+    pub cwd: Option<String>,
+    pub integrated: bool,
+    input_offset: Option<u64>,
+    next_id: u64,
+}
+
+impl CommandTracker {
+    pub fn input_offset(&self) -> Option<u64> {
+        self.input_offset
+    }
+
+    /// Forgets state of the previous shell while keeping its finished records.
+    pub fn shell_restarted(&mut self) {
+        self.current = None;
+        self.cwd = None;
+        self.integrated = false;
+        self.input_offset = None;
+    }
+
+    /// Applies `event` found at scrollback `offset`. `command` is the text typed
+    /// between the prompt end and execution, and is only used for CommandExecuted.
+    /// Returns the record when a command finishes.
+    pub fn apply(&mut self, event: ShellEvent, offset: u64, command: Option<String>) -> Option<CommandRecord> {
+        self.integrated = true;
+
+        match event {
+            ShellEvent::PromptStart => {
+                self.input_offset = None;
+                None
+            }
+            ShellEvent::CommandInputStart => {
+                self.input_offset = Some(offset);
+                None
+            }
+            ShellEvent::CommandExecuted => {
+                self.next_id += 1;
+                self.current = Some(CommandRecord {
+                    id: self.next_id,
+                    command: command.unwrap_or_default(),
+                    cwd: self.cwd.clone(),
+                    exit_code: None,
+                    started_at: now_millis(),
+                    finished_at: None,
+                    output_offset: offset,
+                    output_end_offset: None,
+                });
+                self.input_offset = None;
+                None
+            }
+            ShellEvent::CommandFinished(exit_code) => {
+                let mut record = self.current.take()?;
+                record.exit_code = exit_code;
+                record.finished_at = Some(now_millis());
+                record.output_end_offset = Some(offset);
+
+                if self.records.len() >= MAX_COMMAND_RECORDS {
+                    self.records.pop_front();
+                }
+                self.records.push_back(record.clone());
+                Some(record)
+            }
+            ShellEvent::WorkingDirectory(cwd) => {
+                self.cwd = Some(cwd);
+                None
+            }
+        }
+    }
+}
+
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
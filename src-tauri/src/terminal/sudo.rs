@@ -0,0 +1,144 @@
+use crate::terminal::ansi::strip_ansi;
+use std::time::{Duration, Instant};
+
+// Give up after this many rejected passwords until the user runs another command
+const MAX_REJECTED_ATTEMPTS: u32 = 1;
+const MIN_RETRY_INTERVAL: Duration = Duration::from_secs(2);
+const MAX_TAIL_CHARS: usize = 256;
+
+const REJECTION_MARKERS: &[&str] = &[
+    "sorry, try again",
+    "incorrect password attempt",
+    "authentication failure",
+];
+
+/// Watches terminal output for sudo password prompts and decides when the stored
+/// password may be typed in for the user.
+#[derive(Default)]
+pub struct SudoResponder {
+    // Output after the last newline, where a prompt would be waiting
+    tail: String,
+    // Line the user is currently typing, used to tell whether sudo was just invoked
+    typed_line: String,
+    sudo_invoked: bool,
+    rejected_attempts: u32,
+    last_sent: Option<Instant>,
+}
+
+impl SudoResponder {
+    /// Records a full command line sent to the shell.
+    pub fn note_command(&mut self, command: &str) {
+        self.sudo_invoked = mentions_sudo(command);
+        self.rejected_attempts = 0;
+        self.typed_line.clear();
+    }
+
+    /// Records raw keystrokes sent to the shell.
+    pub fn note_input(&mut self, input: &str) {
+        for c in input.chars() {
+            match c {
+                '\r' | '\n' => {
+                    let line = std::mem::take(&mut self.typed_line);
+                    self.note_command(&line);
+                }
+                '\u{7f}' | '\u{8}' => {
+                    self.typed_line.pop();
+                }
+                // Ctrl+C and Ctrl+U discard the line
+                '\u{3}' | '\u{15}' => self.typed_line.clear(),
+                c if !c.is_control() => self.typed_line.push(c),
+                _ => {}
+            }
+        }
+    }
+
+    /// Feeds shell output and returns true when the password should be sent now.
+    pub fn observe(&mut self, output: &[u8]) -> bool {
+        let text = strip_ansi(&String::from_utf8_lossy(output)).replace('\r', "");
+        self.tail.push_str(&text);
+
+        if let Some(pos) = self.tail.rfind('\n') {
+            let finished = self.tail[..pos].to_lowercase();
+            if self.last_sent.is_some()
+                && REJECTION_MARKERS.iter().any(|m| finished.contains(m))
+            {
+                self.rejected_attempts += 1;
+            }
+            self.tail = self.tail[pos + 1..].to_string();
+        }
+
+        if self.tail.chars().count() > MAX_TAIL_CHARS {
+            let skip = self.tail.chars().count() - MAX_TAIL_CHARS;
+            self.tail = self.tail.chars().skip(skip).collect();
+        }
+
+        if !self.is_prompt(self.tail.trim_end()) {
+            return false;
+        }
+
+        if self.rejected_attempts >= MAX_REJECTED_ATTEMPTS {
+            return false;
+        }
+
+        if let Some(last_sent) = self.last_sent {
+            if last_sent.elapsed() < MIN_RETRY_INTERVAL {
+                return false;
+            }
+        }
+
+        // The prompt is consumed; a re-prompt has to arrive as new output, and
+        // only the next sudo command gets another answer
+        self.tail.clear();
+        self.sudo_invoked = false;
+        self.last_sent = Some(Instant::now());
+        true
+    }
+
+    // Only the first prompt after a sudo command is answered, since sudo_invoked is
+    // reset once the password went out; a later "password:" on the same line may
+    // belong to ssh, mysql or anything else
+    fn is_prompt(&self, line: &str) -> bool {
+        if !self.sudo_invoked || !line.ends_with(':') {
+            return false;
+        }
+
+        // sudo's default prompt keeps the "[sudo]" tag in every locale
+        if line.starts_with("[sudo] ") {
+            return true;
+        }
+
+        // Custom `sudo -p` prompts can't be recognized by their text alone
+        let lower = line.to_lowercase();
+        lower.contains("password") || lower.contains("passwort")
+    }
+}
+
+fn mentions_sudo(command: &str) -> bool {
+    command
+        .split(|c: char| c.is_whitespace() || c == ';' || c == '&' || c == '|' || c == '(')
+        .any(|word| word == "sudo" || word.ends_with("/sudo"))
+}
+
+// Shorter secrets would mangle ordinary output more often than they leak
+const MIN_REDACTED_LEN: usize = 4;
+
+/// Replaces every occurrence of `secret` in `output` so it never reaches the scrollback.
+pub fn redact(output: &[u8], secret: &str) -> Vec<u8> {
+    let secret = secret.as_bytes();
+    if secret.len() < MIN_REDACTED_LEN || output.len() < secret.len() {
+        return output.to_vec();
+    }
+
+    let mut redacted = Vec::with_capacity(output.len());
+    let mut i = 0;
+    while i < output.len() {
+        if output[i..].starts_with(secret) {
+            redacted.extend_from_slice(b"********");
+            i += secret.len();
+        } else {
+            redacted.push(output[i]);
+            i += 1;
+        }
+    }
+    redacted
+}
@@ -1,26 +1,175 @@
 import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
 
 export interface TerminalLine {
 	type: "command" | "output" | "error";
 	content: string;
 }
 
+interface TerminalOutputEvent {
+	key: string;
+	terminal_id: string;
+	data: string;
+	offset: number;
+	next_offset: number;
+}
+
+interface TerminalCwdEvent {
+	key: string;
+	terminal_id: string;
+	cwd: string;
+}
+
+interface TerminalExit {
+	exit_status: number | null;
+	signal: string | null;
+	core_dumped: boolean;
+	error: string | null;
+}
+
+interface TerminalExitEvent {
+	key: string;
+	terminal_id: string;
+	exit: TerminalExit;
+}
+
+interface TerminalInfo {
+	id: string;
+	path: string;
+	cwd: string;
+}
+
+interface TerminalContent {
+	data: string;
+	offset: number;
+	next_offset: number;
+	truncated: boolean;
+}
+
 export interface Terminal {
 	id: string;
 	path: string;
+	cwd: string; // Follows the shell's working directory when shell integration is active
 	history: TerminalLine[];
 	rawContent: string; // Store raw terminal output
-	lastProcessedLength: number; // Track what we've already processed
+	nextOffset: number; // Scrollback offset of the next byte we haven't seen
+}
+
+// Offsets count bytes of UTF-8, so overlapping output is cut on the encoded form
+function unseenPart(data: string, offset: number, seenUntil: number): string {
+	if (seenUntil <= offset) return data;
+	const bytes = new TextEncoder().encode(data);
+	return new TextDecoder().decode(bytes.subarray(seenUntil - offset));
 }
 
 class TerminalManager {
 	terminals = $state<Record<string, Terminal>>({});
 	projectKey = $state<string>("");
+	private unlistenOutput: UnlistenFn | null = null;
+	private unlistenCwd: UnlistenFn | null = null;
+	private unlistenExit: UnlistenFn | null = null;
+	// Terminals with a backfill in flight, mapped to whether another one is needed after it
+	private backfilling = new Map<string, boolean>();
 
 	setProjectKey(key: string) {
 		this.projectKey = key;
 	}
 
+	private async subscribeOutput() {
+		if (this.unlistenOutput) return;
+
+		this.unlistenOutput = await listen<TerminalOutputEvent>("terminal-output", (event) => {
+			const { key, terminal_id, data, offset, next_offset } = event.payload;
+			if (key !== this.projectKey) return;
+
+			// Output of a terminal that is still being created is fetched once it is added
+			const terminal = this.terminals[terminal_id];
+			if (!terminal) return;
+
+			// Missed output in between has to be fetched, this event included
+			if (offset > terminal.nextOffset) {
+				this.backfill(terminal);
+				return;
+			}
+
+			// Skip output already loaded through get_terminal_content
+			if (next_offset > terminal.nextOffset) {
+				this.appendOutput(terminal, unseenPart(data, offset, terminal.nextOffset));
+				terminal.nextOffset = next_offset;
+			}
+		});
+
+		this.unlistenCwd = await listen<TerminalCwdEvent>("terminal-cwd-changed", (event) => {
+			const { key, terminal_id, cwd } = event.payload;
+			if (key !== this.projectKey) return;
+
+			const terminal = this.terminals[terminal_id];
+			if (terminal) {
+				terminal.cwd = cwd;
+				this.terminals = { ...this.terminals };
+			}
+		});
+
+		this.unlistenExit = await listen<TerminalExitEvent>("terminal-exited", (event) => {
+			const { key, terminal_id, exit } = event.payload;
+			if (key !== this.projectKey) return;
+
+			const terminal = this.terminals[terminal_id];
+			if (terminal) {
+				const reason = exit.error
+					?? (exit.signal ? `signal ${exit.signal}` : `status ${exit.exit_status ?? "unknown"}`);
+				terminal.history.push({
+					type: "error",
+					content: `Shell exited (${reason})`,
+				});
+				this.terminals = { ...this.terminals };
+			}
+		});
+	}
+
+	// Loads everything buffered after the terminal's last seen offset
+	private async backfill(terminal: Terminal) {
+		if (this.backfilling.has(terminal.id)) {
+			this.backfilling.set(terminal.id, true);
+			return;
+		}
+
+		try {
+			do {
+				this.backfilling.set(terminal.id, false);
+				const content = await invoke<TerminalContent>("get_terminal_content", {
+					key: this.projectKey,
+					terminalId: terminal.id,
+					since: terminal.nextOffset,
+				});
+
+				if (content.next_offset > terminal.nextOffset) {
+					this.appendOutput(terminal, unseenPart(content.data, content.offset, terminal.nextOffset));
+					terminal.nextOffset = content.next_offset;
+				}
+			} while (this.backfilling.get(terminal.id));
+		} catch (e) {
+			console.error(`Failed to load content for terminal ${terminal.id}:`, e);
+		} finally {
+			this.backfilling.delete(terminal.id);
+		}
+	}
+
+	async restartTerminal(terminalId: string) {
+		await invoke("restart_terminal", {
+			key: this.projectKey,
+			terminalId,
+		});
+	}
+
+	async changeDirectory(terminalId: string, path: string) {
+		await invoke("change_terminal_directory", {
+			key: this.projectKey,
+			terminalId,
+			path,
+		});
+	}
+
 	async createTerminal(path: string): Promise<Terminal> {
 		try {
 			console.log("Creating terminal at path:", path);
@@ -34,16 +183,19 @@ class TerminalManager {
 			const terminal: Terminal = {
 				id: terminalId,
 				path,
+				cwd: path,
 				history: [],
 				rawContent: "",
-				lastProcessedLength: 0,
+				nextOffset: 0,
 			};
 
 			// Add to map
 			this.terminals[terminalId] = terminal;
 
-			// Start polling for this terminal
-			this.startPolling(terminalId);
+			// Output is pushed by the backend; anything sent before the terminal was
+			// added here, like the motd or first prompt, is fetched once
+			await this.subscribeOutput();
+			await this.backfill(terminal);
 
 			return terminal;
 		} catch (error) {
@@ -54,7 +206,9 @@ class TerminalManager {
 
 	async loadTerminals() {
 		try {
-			const terminalList = await invoke<Array<[string, string]>>("list_terminals", {
+			await this.subscribeOutput();
+
+			const terminalList = await invoke<TerminalInfo[]>("list_terminals", {
 				key: this.projectKey,
 			});
 
@@ -64,32 +218,30 @@ class TerminalManager {
 			this.terminals = {};
 
 			// Add loaded terminals
-			for (const [id, path] of terminalList) {
+			for (const { id, path, cwd } of terminalList) {
 				const terminal: Terminal = {
 					id,
 					path,
+					cwd,
 					history: [],
 					rawContent: "",
-					lastProcessedLength: 0,
+					nextOffset: 0,
 				};
 				this.terminals[id] = terminal;
 				
 				// Load existing content
 				try {
-					const content = await invoke<string>("get_terminal_content", {
+					const content = await invoke<TerminalContent>("get_terminal_content", {
 						key: this.projectKey,
 						terminalId: id,
 					});
-					
-					terminal.rawContent = content;
-					terminal.history = this.parseTerminalOutput(content);
-					terminal.lastProcessedLength = content.length;
+
+					terminal.rawContent = content.data;
+					terminal.history = this.parseTerminalOutput(content.data);
+					terminal.nextOffset = content.next_offset;
 				} catch (e) {
 					console.error(`Failed to load content for terminal ${id}:`, e);
 				}
-
-				// Start polling
-				this.startPolling(id);
 			}
 
 			// Force reactivity update
@@ -121,9 +273,6 @@ class TerminalManager {
 			});
 
 			console.log("Command executed:", command);
-
-			// Poll for output immediately after a short delay
-			setTimeout(() => this.pollTerminalOutput(terminalId), 200);
 		} catch (error) {
 			console.error("Failed to execute command:", error);
 			terminal.history.push({
@@ -152,74 +301,34 @@ class TerminalManager {
 		return lines;
 	}
 
-	private startPolling(terminalId: string) {
-		// Poll every 300ms for new output
-		const poll = async () => {
-			if (!this.terminals[terminalId]) {
-				return; // Terminal was closed
+	private appendOutput(terminal: Terminal, newContent: string) {
+		terminal.rawContent += newContent;
+
+		// Parse new lines and add to history
+		const newLines = newContent.split('\n').filter(line => line.trim());
+		let lastExecutedCommands = [];
+		for (const line of terminal.history.slice().reverse()) {
+			if (line.type === "command") {
+				lastExecutedCommands.push(line.content.trim());
+			} else {
+				break;
 			}
-			await this.pollTerminalOutput(terminalId);
-			setTimeout(poll, 300);
-		};
-		
-		setTimeout(poll, 300);
-	}
+		}
 
-	private async pollTerminalOutput(terminalId: string) {
-		const terminal = this.terminals[terminalId];
-		
-		if (!terminal) return;
+		for (const line of newLines) {
+			if (lastExecutedCommands.includes(line.trim())) {
+				// Skip echo of last executed commands
+				continue;
+			}
 
-		try {
-			const content = await invoke<string>("get_terminal_content", {
-				key: this.projectKey,
-				terminalId,
+			terminal.history.push({
+				type: "output",
+				content: line.trim(),
 			});
-
-			// Check if there's new content
-			if (content.length > terminal.lastProcessedLength) {
-				// Get only the new content
-				const newContent = content.substring(terminal.lastProcessedLength);
-				terminal.rawContent = content;
-				console.log("New terminal content:", newContent);
-				// Parse new lines and add to history
-				const newLines = newContent.split('\n').filter(line => line.trim());
-				let lastExecutedCommands = [];
-				for (const line of terminal.history.slice().reverse()) {
-					if (line.type === "command") {
-						lastExecutedCommands.push(line.content.trim());
-					} else {
-						break;
-					}
-				}
-
-				for (const line of newLines) {
-					if (lastExecutedCommands.includes(line.trim())) {
-						// Skip echo of last executed commands
-						continue;
-					}
-
-					console.log({
-						lastExecutedCommands,
-						line,
-					})
-					terminal.history.push({
-						type: "output",
-						content: line.trim(),
-					});
-				}
-
-				console.log("Updated terminal history:", terminal.history);
-
-				terminal.lastProcessedLength = content.length;
-
-				// Force update
-				this.terminals = { ...this.terminals };
-			}
-		} catch (error) {
-			// Silently fail - terminal might be closing
-			console.debug("Failed to poll terminal output:", error);
 		}
+
+		// Force update
+		this.terminals = { ...this.terminals };
 	}
 
 	async closeTerminal(terminalId: string) {
@@ -241,6 +350,13 @@ class TerminalManager {
 	}
 
 	clearAll() {
+		this.unlistenOutput?.();
+		this.unlistenOutput = null;
+		this.unlistenCwd?.();
+		this.unlistenCwd = null;
+		this.unlistenExit?.();
+		this.unlistenExit = null;
+
 		// Close all terminals
 		for (const terminalId of Object.keys(this.terminals)) {
 			this.closeTerminal(terminalId).catch(console.error);
@@ -248,4 +364,4 @@ class TerminalManager {
 	}
 }
 
-export const terminalManager = new TerminalManager();
\ No newline at end of file
+export const terminalManager = new TerminalManager();